@@ -0,0 +1,16 @@
+-- This file should undo anything in `up.sql`
+-- SQLite cannot drop a column used in a foreign key, so rebuild the table
+CREATE TABLE timer_runs_old (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    user TEXT NOT NULL,
+    working_time_secs INTEGER NOT NULL,
+    breaking_time_secs INTEGER NOT NULL,
+    date DATE NOT NULL,
+    note TEXT
+);
+INSERT INTO timer_runs_old (id, user, working_time_secs, breaking_time_secs, date, note)
+SELECT id, user, working_time_secs, breaking_time_secs, date, note FROM timer_runs;
+DROP TABLE timer_runs;
+ALTER TABLE timer_runs_old RENAME TO timer_runs;
+
+DROP TABLE tasks;
@@ -0,0 +1,31 @@
+-- This file should undo anything in `up.sql`
+CREATE TABLE timer_runs_old (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    user TEXT NOT NULL,
+    working_time_secs INTEGER NOT NULL,
+    breaking_time_secs INTEGER NOT NULL,
+    date DATE NOT NULL,
+    note TEXT,
+    task_id INTEGER REFERENCES tasks(id)
+);
+INSERT INTO timer_runs_old (id, user, working_time_secs, breaking_time_secs, date, note, task_id)
+SELECT timer_runs.id, users.name, working_time_secs, breaking_time_secs, date, note, task_id
+FROM timer_runs JOIN users ON users.id = timer_runs.user_id;
+DROP TABLE timer_runs;
+ALTER TABLE timer_runs_old RENAME TO timer_runs;
+
+CREATE TABLE tasks_old (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    user TEXT NOT NULL,
+    name TEXT NOT NULL,
+    estimated_pomodoros INTEGER NOT NULL,
+    created DATE NOT NULL,
+    UNIQUE (user, name)
+);
+INSERT INTO tasks_old (id, user, name, estimated_pomodoros, created)
+SELECT tasks.id, users.name, tasks.name, tasks.estimated_pomodoros, tasks.created
+FROM tasks JOIN users ON users.id = tasks.user_id;
+DROP TABLE tasks;
+ALTER TABLE tasks_old RENAME TO tasks;
+
+DROP TABLE users;
@@ -0,0 +1,43 @@
+-- Your SQL goes here
+CREATE TABLE users (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    settings TEXT
+);
+
+-- Every name used so far becomes a user
+INSERT INTO users (name)
+SELECT user FROM timer_runs
+UNION
+SELECT user FROM tasks;
+
+-- SQLite cannot change columns in place, so rebuild the tables with a user_id
+CREATE TABLE tasks_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    user_id INTEGER NOT NULL REFERENCES users(id),
+    name TEXT NOT NULL,
+    estimated_pomodoros INTEGER NOT NULL,
+    created DATE NOT NULL,
+    UNIQUE (user_id, name)
+);
+INSERT INTO tasks_new (id, user_id, name, estimated_pomodoros, created)
+SELECT tasks.id, users.id, tasks.name, tasks.estimated_pomodoros, tasks.created
+FROM tasks JOIN users ON users.name = tasks.user;
+DROP TABLE tasks;
+ALTER TABLE tasks_new RENAME TO tasks;
+
+CREATE TABLE timer_runs_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    user_id INTEGER NOT NULL REFERENCES users(id),
+    working_time_secs INTEGER NOT NULL,  -- Store Duration as seconds
+    breaking_time_secs INTEGER NOT NULL, -- Store Duration as seconds
+    date DATE NOT NULL,
+    note TEXT,
+    task_id INTEGER REFERENCES tasks(id)
+);
+INSERT INTO timer_runs_new (id, user_id, working_time_secs, breaking_time_secs, date, note, task_id)
+SELECT timer_runs.id, users.id, working_time_secs, breaking_time_secs, date, note, task_id
+FROM timer_runs JOIN users ON users.name = timer_runs.user;
+DROP TABLE timer_runs;
+ALTER TABLE timer_runs_new RENAME TO timer_runs;
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX timer_runs_uuid;
+ALTER TABLE timer_runs DROP COLUMN uuid;
@@ -0,0 +1,14 @@
+-- Your SQL goes here
+-- A stable id for each run, so histories from several machines can be merged
+ALTER TABLE timer_runs ADD COLUMN uuid TEXT NOT NULL DEFAULT '';
+
+-- Random version 4 UUIDs for the runs recorded so far
+UPDATE timer_runs SET uuid = lower(
+    hex(randomblob(4)) || '-' ||
+    hex(randomblob(2)) || '-' ||
+    '4' || substr(hex(randomblob(2)), 2) || '-' ||
+    substr('89ab', 1 + (abs(random()) % 4), 1) || substr(hex(randomblob(2)), 2) || '-' ||
+    hex(randomblob(6))
+);
+
+CREATE UNIQUE INDEX timer_runs_uuid ON timer_runs (uuid);
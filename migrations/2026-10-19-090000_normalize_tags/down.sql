@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+-- Tags merged by case can not be told apart again
+SELECT 1;
@@ -0,0 +1,16 @@
+-- Your SQL goes here
+-- Tags are stored trimmed and lowercase: move runs to the first of the tags that only differ in
+-- case or spaces, drop the others and normalize the names of the ones left
+INSERT OR IGNORE INTO timer_run_tags (timer_run_id, tag_id)
+SELECT timer_run_tags.timer_run_id,
+       (SELECT MIN(same.id) FROM tags AS same WHERE LOWER(TRIM(same.name)) = LOWER(TRIM(tags.name)))
+FROM timer_run_tags
+JOIN tags ON tags.id = timer_run_tags.tag_id;
+
+DELETE FROM timer_run_tags
+WHERE tag_id NOT IN (SELECT MIN(id) FROM tags GROUP BY LOWER(TRIM(name)));
+
+DELETE FROM tags
+WHERE id NOT IN (SELECT MIN(id) FROM tags GROUP BY LOWER(TRIM(name)));
+
+UPDATE tags SET name = LOWER(TRIM(name));
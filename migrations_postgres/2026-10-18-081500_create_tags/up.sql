@@ -0,0 +1,11 @@
+-- Your SQL goes here
+CREATE TABLE tags (
+    id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE timer_run_tags (
+    timer_run_id INTEGER NOT NULL REFERENCES timer_runs(id) ON DELETE CASCADE,
+    tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    PRIMARY KEY (timer_run_id, tag_id)
+);
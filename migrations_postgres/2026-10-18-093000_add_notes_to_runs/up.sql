@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE timer_runs ADD COLUMN note TEXT;
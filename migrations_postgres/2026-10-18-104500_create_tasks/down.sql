@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE timer_runs DROP COLUMN task_id;
+DROP TABLE tasks;
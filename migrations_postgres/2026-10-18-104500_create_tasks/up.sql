@@ -0,0 +1,11 @@
+-- Your SQL goes here
+CREATE TABLE tasks (
+    id SERIAL PRIMARY KEY,
+    "user" TEXT NOT NULL,
+    name TEXT NOT NULL,
+    estimated_pomodoros INTEGER NOT NULL,
+    created DATE NOT NULL,
+    UNIQUE ("user", name)
+);
+
+ALTER TABLE timer_runs ADD COLUMN task_id INTEGER REFERENCES tasks(id);
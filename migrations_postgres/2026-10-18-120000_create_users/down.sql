@@ -0,0 +1,13 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE timer_runs ADD COLUMN "user" TEXT;
+UPDATE timer_runs SET "user" = users.name FROM users WHERE users.id = timer_runs.user_id;
+ALTER TABLE timer_runs ALTER COLUMN "user" SET NOT NULL;
+ALTER TABLE timer_runs DROP COLUMN user_id;
+
+ALTER TABLE tasks ADD COLUMN "user" TEXT;
+UPDATE tasks SET "user" = users.name FROM users WHERE users.id = tasks.user_id;
+ALTER TABLE tasks ALTER COLUMN "user" SET NOT NULL;
+ALTER TABLE tasks DROP COLUMN user_id;
+ALTER TABLE tasks ADD UNIQUE ("user", name);
+
+DROP TABLE users;
@@ -0,0 +1,24 @@
+-- Your SQL goes here
+CREATE TABLE users (
+    id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMP NOT NULL DEFAULT (CURRENT_TIMESTAMP AT TIME ZONE 'UTC'),
+    settings TEXT
+);
+
+-- Every name used so far becomes a user
+INSERT INTO users (name)
+SELECT "user" FROM timer_runs
+UNION
+SELECT "user" FROM tasks;
+
+ALTER TABLE tasks ADD COLUMN user_id INTEGER REFERENCES users(id);
+UPDATE tasks SET user_id = users.id FROM users WHERE users.name = tasks."user";
+ALTER TABLE tasks ALTER COLUMN user_id SET NOT NULL;
+ALTER TABLE tasks DROP COLUMN "user";
+ALTER TABLE tasks ADD UNIQUE (user_id, name);
+
+ALTER TABLE timer_runs ADD COLUMN user_id INTEGER REFERENCES users(id);
+UPDATE timer_runs SET user_id = users.id FROM users WHERE users.name = timer_runs."user";
+ALTER TABLE timer_runs ALTER COLUMN user_id SET NOT NULL;
+ALTER TABLE timer_runs DROP COLUMN "user";
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX timer_run_tags_tag_id;
+DROP INDEX timer_runs_user_id_date;
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+CREATE INDEX timer_runs_user_id_date ON timer_runs (user_id, date);
+CREATE INDEX timer_run_tags_tag_id ON timer_run_tags (tag_id);
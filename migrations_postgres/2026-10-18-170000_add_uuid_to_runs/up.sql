@@ -0,0 +1,7 @@
+-- Your SQL goes here
+-- A stable id for each run, so histories from several machines can be merged
+ALTER TABLE timer_runs ADD COLUMN uuid TEXT;
+UPDATE timer_runs SET uuid = gen_random_uuid()::text;
+ALTER TABLE timer_runs ALTER COLUMN uuid SET NOT NULL;
+
+CREATE UNIQUE INDEX timer_runs_uuid ON timer_runs (uuid);
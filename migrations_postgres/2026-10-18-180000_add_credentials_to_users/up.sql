@@ -0,0 +1,5 @@
+-- Your SQL goes here
+-- Optional password, as an Argon2 hash, and the lockout after repeated wrong passwords
+ALTER TABLE users ADD COLUMN password_hash TEXT;
+ALTER TABLE users ADD COLUMN failed_sign_ins INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE users ADD COLUMN locked_until TIMESTAMP;
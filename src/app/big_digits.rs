@@ -0,0 +1,102 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Style;
+use ratatui::widgets::Widget;
+
+/// Rows of every glyph
+const GLYPH_HEIGHT: u16 = 5;
+
+/// Glyphs of the font, `#` being a filled pixel. Digits are 3 pixels wide, the colon 1.
+fn glyph(c: char) -> [&'static str; GLYPH_HEIGHT as usize] {
+    match c {
+        '0' => ["###", "# #", "# #", "# #", "###"],
+        '1' => ["  #", "  #", "  #", "  #", "  #"],
+        '2' => ["###", "  #", "###", "#  ", "###"],
+        '3' => ["###", "  #", "###", "  #", "###"],
+        '4' => ["# #", "# #", "###", "  #", "  #"],
+        '5' => ["###", "#  ", "###", "  #", "###"],
+        '6' => ["###", "#  ", "###", "# #", "###"],
+        '7' => ["###", "  #", "  #", "  #", "  #"],
+        '8' => ["###", "# #", "###", "# #", "###"],
+        '9' => ["###", "# #", "###", "  #", "###"],
+        ':' => [" ", "#", " ", "#", " "],
+        _ => ["   ", "   ", "   ", "   ", "   "],
+    }
+}
+
+/// Text like `24:59` in big block digits, drawn as large as fits the area and centred in it.
+/// A pixel is twice as wide as it is high, so digits look about square in a terminal.
+/// When even the smallest size does not fit, the text is drawn as it is.
+pub struct BigDigits<'a> {
+    text: &'a str,
+    style: Style,
+}
+
+impl<'a> BigDigits<'a> {
+    pub fn new(text: &'a str) -> Self {
+        BigDigits {
+            text,
+            style: Style::default(),
+        }
+    }
+
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Width and height in cells when every pixel is `scale` rows high, with one pixel
+    /// between glyphs
+    fn size(&self, scale: u16) -> (u16, u16) {
+        let pixels: usize = self.text.chars().map(|c| glyph(c)[0].len()).sum();
+        let gaps = self.text.chars().count().saturating_sub(1);
+
+        (
+            ((pixels + gaps) as u16).saturating_mul(2 * scale),
+            GLYPH_HEIGHT * scale,
+        )
+    }
+}
+
+impl Widget for BigDigits<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let fits = |scale: u16| {
+            let (width, height) = self.size(scale);
+            width <= area.width && height <= area.height
+        };
+        let Some(scale) = (1..=area.height).take_while(|scale| fits(*scale)).last() else {
+            let width = (self.text.chars().count() as u16).min(area.width);
+            let x = area.x + (area.width - width) / 2;
+            let y = area.y + area.height.saturating_sub(1) / 2;
+            if area.height > 0 {
+                buf.set_stringn(x, y, self.text, width as usize, self.style);
+            }
+            return;
+        };
+
+        let (width, height) = self.size(scale);
+        let mut x = area.x + (area.width - width) / 2;
+        let y = area.y + (area.height - height) / 2;
+
+        for c in self.text.chars() {
+            let rows = glyph(c);
+            for (row, pixels) in rows.iter().enumerate() {
+                for (column, pixel) in pixels.chars().enumerate() {
+                    if pixel != '#' {
+                        continue;
+                    }
+                    let left = x + column as u16 * 2 * scale;
+                    let top = y + row as u16 * scale;
+                    for cell_y in top..top + scale {
+                        for cell_x in left..left + 2 * scale {
+                            if let Some(cell) = buf.cell_mut((cell_x, cell_y)) {
+                                cell.set_symbol("█").set_style(self.style);
+                            }
+                        }
+                    }
+                }
+            }
+            x += (rows[0].len() as u16 + 1) * 2 * scale;
+        }
+    }
+}
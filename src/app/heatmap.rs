@@ -0,0 +1,140 @@
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Style;
+use ratatui::widgets::Widget;
+use std::collections::BTreeMap;
+
+/// Columns taken by the weekday labels in front of the rows
+const LABEL_WIDTH: usize = 4;
+
+/// Shades from no work to the most worked day, lightest first
+const SHADES: [char; 5] = ['·', '░', '▒', '▓', '█'];
+
+/// A year of daily working time, GitHub style: a column per week and a row per weekday, with
+/// every day shaded by its working time relative to the year's best day.
+/// The same lines are drawn in the TUI and printed by `pomodorotimer heatmap`.
+pub struct Heatmap<'a> {
+    year: i32,
+    week_start: Weekday,
+    /// Working seconds per day, days without runs may be left out
+    days: &'a BTreeMap<NaiveDate, i64>,
+    style: Style,
+}
+
+impl<'a> Heatmap<'a> {
+    pub fn new(year: i32, week_start: Weekday, days: &'a BTreeMap<NaiveDate, i64>) -> Self {
+        Heatmap {
+            year,
+            week_start,
+            days,
+            style: Style::default(),
+        }
+    }
+
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Working seconds of the whole year
+    pub fn total_secs(&self) -> i64 {
+        self.year_days().map(|(_, secs)| secs).sum()
+    }
+
+    /// Month names, a line per weekday and a legend, as the heatmap is drawn
+    pub fn lines(&self) -> Vec<String> {
+        let (Some(first), Some(last)) = (
+            NaiveDate::from_ymd_opt(self.year, 1, 1),
+            NaiveDate::from_ymd_opt(self.year, 12, 31),
+        ) else {
+            return Vec::new();
+        };
+        let grid_start = first - Days::new(first.weekday().days_since(self.week_start) as u64);
+        let column = |day: NaiveDate| ((day - grid_start).num_days() / 7) as usize;
+        let max = self.year_days().map(|(_, secs)| secs).max().unwrap_or(0);
+
+        let mut months = vec![' '; LABEL_WIDTH + column(last) + 1];
+        let mut free_from = 0;
+        for month in 1..=12 {
+            let Some(start) = NaiveDate::from_ymd_opt(self.year, month, 1) else {
+                continue;
+            };
+            let at = LABEL_WIDTH + column(start);
+            let name = start.format("%b").to_string();
+            if at >= free_from && at + name.len() <= months.len() {
+                months.splice(at..at + name.len(), name.chars());
+                free_from = at + name.len() + 1;
+            }
+        }
+
+        let mut rows = vec![vec![' '; column(last) + 1]; 7];
+        for day in first.iter_days().take_while(|day| *day <= last) {
+            let secs = self.days.get(&day).copied().unwrap_or(0);
+            rows[day.weekday().days_since(self.week_start) as usize][column(day)] =
+                Heatmap::shade(secs, max);
+        }
+
+        let mut lines = vec![months
+            .into_iter()
+            .collect::<String>()
+            .trim_end()
+            .to_string()];
+        let mut weekday = self.week_start;
+        for (row, cells) in rows.into_iter().enumerate() {
+            let label = if row % 2 == 0 {
+                weekday.to_string()
+            } else {
+                String::new()
+            };
+            lines.push(format!(
+                "{:<width$}{}",
+                label,
+                cells.into_iter().collect::<String>(),
+                width = LABEL_WIDTH
+            ));
+            weekday = weekday.succ();
+        }
+        lines.push(format!(
+            "{:<width$}Less {} More",
+            "",
+            SHADES
+                .iter()
+                .map(char::to_string)
+                .collect::<Vec<_>>()
+                .join(" "),
+            width = LABEL_WIDTH
+        ));
+        lines
+    }
+
+    /// Shade of a day, by quarters of the best day's working time
+    fn shade(secs: i64, max: i64) -> char {
+        if secs <= 0 || max <= 0 {
+            return SHADES[0];
+        }
+        let quarter = (secs * 4 + max - 1) / max;
+        SHADES[quarter.clamp(1, 4) as usize]
+    }
+
+    fn year_days(&self) -> impl Iterator<Item = (&NaiveDate, i64)> {
+        self.days
+            .iter()
+            .filter(|(day, _)| day.year() == self.year)
+            .map(|(day, secs)| (day, *secs))
+    }
+}
+
+impl Widget for Heatmap<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        for (row, line) in self.lines().iter().enumerate().take(area.height as usize) {
+            buf.set_stringn(
+                area.x,
+                area.y + row as u16,
+                line,
+                area.width as usize,
+                self.style,
+            );
+        }
+    }
+}
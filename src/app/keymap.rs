@@ -0,0 +1,292 @@
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// What a key does outside of the input box
+#[derive(PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Debug)]
+pub enum Action {
+    EnterCommand,
+    StartPause,
+    Stop,
+    Skip,
+    Extend,
+    Shorten,
+    Dashboard,
+    Heatmap,
+    PreviousYear,
+    NextYear,
+    Help,
+    Quit,
+}
+
+impl Action {
+    pub const ALL: [Action; 12] = [
+        Action::EnterCommand,
+        Action::StartPause,
+        Action::Stop,
+        Action::Skip,
+        Action::Extend,
+        Action::Shorten,
+        Action::Dashboard,
+        Action::Heatmap,
+        Action::PreviousYear,
+        Action::NextYear,
+        Action::Help,
+        Action::Quit,
+    ];
+
+    /// Name of the action in the keymap file
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::EnterCommand => "enter-command",
+            Action::StartPause => "start-pause",
+            Action::Stop => "stop",
+            Action::Skip => "skip",
+            Action::Extend => "extend",
+            Action::Shorten => "shorten",
+            Action::Dashboard => "dashboard",
+            Action::Heatmap => "heatmap",
+            Action::PreviousYear => "previous-year",
+            Action::NextYear => "next-year",
+            Action::Help => "help",
+            Action::Quit => "quit",
+        }
+    }
+
+    /// What the action does, for the help overlay
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::EnterCommand => "Enter a command",
+            Action::StartPause => "Start a session, or pause and resume it",
+            Action::Stop => "Stop the session",
+            Action::Skip => "Skip to the next phase",
+            Action::Extend => "Add a minute to the current phase",
+            Action::Shorten => "Take a minute from the current phase",
+            Action::Dashboard => "Show or hide the dashboard",
+            Action::Heatmap => "Show or hide the heatmap",
+            Action::PreviousYear => "Heatmap of the year before",
+            Action::NextYear => "Heatmap of the year after",
+            Action::Help => "Show or hide this help",
+            Action::Quit => "Quit",
+        }
+    }
+
+    /// Whether the action has to keep a key, as there is no other way to enter commands or quit
+    fn is_required(&self) -> bool {
+        matches!(self, Action::EnterCommand | Action::Quit)
+    }
+
+    fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    fn default_keys(&self) -> &'static [&'static str] {
+        match self {
+            Action::EnterCommand => &["e"],
+            Action::StartPause => &["space"],
+            Action::Stop => &["s"],
+            Action::Skip => &["n"],
+            Action::Extend => &["+"],
+            Action::Shorten => &["-"],
+            Action::Dashboard => &["d"],
+            Action::Heatmap => &["h"],
+            Action::PreviousYear => &["left"],
+            Action::NextYear => &["right"],
+            Action::Help => &["?"],
+            Action::Quit => &["q"],
+        }
+    }
+}
+
+/// A key with the Ctrl and Alt modifiers it has to be pressed with, written like `q`,
+/// `space`, `left`, `f1` or `ctrl-x`. Shift is part of the character, like `+` or `G`.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct Key {
+    code: KeyCode,
+    ctrl: bool,
+    alt: bool,
+}
+
+impl Key {
+    pub fn parse(text: &str) -> Option<Key> {
+        let mut key = Key {
+            code: KeyCode::Null,
+            ctrl: false,
+            alt: false,
+        };
+        let mut rest = text;
+        loop {
+            if let Some(after) = rest.strip_prefix("ctrl-") {
+                key.ctrl = true;
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix("alt-") {
+                key.alt = true;
+                rest = after;
+            } else {
+                break;
+            }
+        }
+
+        let mut chars = rest.chars();
+        key.code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_lowercase().as_str() {
+                "space" => KeyCode::Char(' '),
+                "enter" => KeyCode::Enter,
+                "esc" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backspace" => KeyCode::Backspace,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                function => KeyCode::F(function.strip_prefix('f')?.parse().ok()?),
+            },
+        };
+        Some(key)
+    }
+
+    fn matches(&self, event: &KeyEvent) -> bool {
+        self.code == event.code
+            && self.ctrl == event.modifiers.contains(KeyModifiers::CONTROL)
+            && self.alt == event.modifiers.contains(KeyModifiers::ALT)
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl-")?;
+        }
+        if self.alt {
+            write!(f, "Alt-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+/// The keys of every action outside of the input box
+#[derive(PartialEq, Debug)]
+pub struct Keymap {
+    keys: BTreeMap<Action, Vec<Key>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap {
+            keys: Action::ALL
+                .into_iter()
+                .map(|action| {
+                    let keys = action
+                        .default_keys()
+                        .iter()
+                        .filter_map(|key| Key::parse(key))
+                        .collect();
+                    (action, keys)
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Keymap {
+    /// Where the keymap is read from without `--keymap`:
+    /// `$XDG_CONFIG_HOME/pomodorotimer/keymap.toml`
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("pomodorotimer").join("keymap.toml"))
+    }
+
+    /// Read the keymap file, the defaults when there is none
+    pub fn load(path: &Path) -> Result<Keymap, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => Keymap::from_toml(&text)
+                .map_err(|e| format!("Error in keymap {}: {}", path.display(), e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Keymap::default()),
+            Err(e) => Err(format!("Could not read keymap {}: {}", path.display(), e)),
+        }
+    }
+
+    /// Keymap from a table of action names and their keys, like `quit = ["q", "ctrl-c"]`.
+    /// Actions left out keep their default keys, and an empty list leaves one without a key,
+    /// except for entering commands and quitting. A key bound to more than one action is refused.
+    pub fn from_toml(text: &str) -> Result<Keymap, String> {
+        let table: BTreeMap<String, Vec<String>> =
+            toml::from_str(text).map_err(|e| e.message().to_string())?;
+
+        let mut keymap = Keymap::default();
+        for (name, keys) in table {
+            let Some(action) = Action::from_name(&name) else {
+                return Err(format!("Unknown action {:?}", name));
+            };
+            let keys = keys
+                .iter()
+                .map(|key| Key::parse(key).ok_or_else(|| format!("Unknown key {:?}", key)))
+                .collect::<Result<Vec<Key>, String>>()?;
+            if keys.is_empty() && action.is_required() {
+                return Err(format!("{} needs at least one key", name));
+            }
+            keymap.keys.insert(action, keys);
+        }
+
+        keymap.check_conflicts()?;
+        Ok(keymap)
+    }
+
+    /// The action of the pressed key, if it has one
+    pub fn action(&self, event: &KeyEvent) -> Option<Action> {
+        self.keys
+            .iter()
+            .find(|(_, keys)| keys.iter().any(|key| key.matches(event)))
+            .map(|(action, _)| *action)
+    }
+
+    /// The action's keys, like `q/Ctrl-c`, or `none`
+    pub fn keys(&self, action: Action) -> String {
+        match self.keys.get(&action) {
+            Some(keys) if !keys.is_empty() => keys
+                .iter()
+                .map(Key::to_string)
+                .collect::<Vec<_>>()
+                .join("/"),
+            _ => String::from("none"),
+        }
+    }
+
+    /// Keys and description of every action, in the order of `Action::ALL`
+    pub fn help(&self) -> Vec<(String, &'static str)> {
+        Action::ALL
+            .into_iter()
+            .map(|action| (self.keys(action), action.description()))
+            .collect()
+    }
+
+    fn check_conflicts(&self) -> Result<(), String> {
+        let mut bound: Vec<(Key, Action)> = Vec::new();
+        for (action, keys) in &self.keys {
+            for key in keys {
+                if let Some((_, other)) = bound.iter().find(|(bound_key, _)| bound_key == key) {
+                    return Err(format!(
+                        "{} is bound to both {} and {}",
+                        key,
+                        other.name(),
+                        action.name()
+                    ));
+                }
+                bound.push((*key, *action));
+            }
+        }
+        Ok(())
+    }
+}
@@ -1 +1,4 @@
+pub mod big_digits;
+pub mod heatmap;
+pub mod keymap;
 pub mod tui_app;
@@ -1,22 +1,41 @@
+use crate::app::big_digits::BigDigits;
+use crate::app::heatmap::Heatmap;
+use crate::app::keymap::{Action, Keymap};
 use crate::app::tui_app::MessageType::{InvalidCommand, ValidCommand};
-use crate::core::pomodoro_timer::Period::{AllTime, Today};
-use crate::core::pomodoro_timer::{PomodoroTimer, TimerState};
+use crate::core::models::RunRecord;
+use crate::core::pomodoro_timer::{
+    Period, PomodoroTimer, RunEdit, SignInError, TimerState, DASHBOARD_DAYS, DASHBOARD_WEEKS,
+};
+use crate::db::timer_database::ExportFormat;
+use chrono::{Datelike, NaiveDate, Weekday};
 use ratatui::widgets::Wrap;
 use ratatui::{
     crossterm::event::{self, Event, KeyCode, KeyEventKind},
-    layout::{Constraint, Layout, Position},
+    layout::{Constraint, Layout, Position, Rect},
     style::{Color, Modifier, Style, Stylize},
-    text,
-    text::{Line, Span, Text},
-    widgets::{Block, List, ListItem, Paragraph},
+    symbols, text,
+    text::{Line, Text},
+    widgets::{
+        Bar, BarChart, BarGroup, Block, Clear, LineGauge, List, ListItem, Paragraph, Sparkline,
+    },
     DefaultTerminal, Frame,
 };
+use std::path::Path;
 use std::sync::mpsc;
 use std::sync::mpsc::Sender;
 use std::thread;
 use std::time::Duration;
 use tui_input::{Input, InputRequest};
 
+/// Number of row errors shown after an import
+const MAX_IMPORT_ERRORS: usize = 10;
+/// Tomatoes drawn for today's pomodoros before showing a count instead
+const MAX_TOMATOES: usize = 12;
+/// Runs shown by `log` without a count
+const LOG_LENGTH: usize = 10;
+/// Minutes the `+` and `-` keys add to or take from the running phase
+const KEY_ADJUST_MINUTES: u64 = 1;
+
 pub struct App {
     /// Current value of the input box
     input: Input,
@@ -27,11 +46,86 @@ pub struct App {
     /// The pomodoro timer - Application
     timer: PomodoroTimer,
     prev_message: usize,
+    /// Notices work phases ending to prompt for a note
+    reflection: ReflectionTrigger,
+    /// What is shown below the input box
+    view: View,
+    /// Keys of the actions outside of the input box
+    keymap: Keymap,
+    /// Whether the keys are shown over everything else
+    show_help: bool,
 }
 
 enum InputMode {
     Normal,
     Editing,
+    /// Asking what got done during the work phase that just ended
+    Reflection,
+    /// Asking for a password, which is shown masked
+    Password(PasswordPrompt),
+}
+
+/// What a password is asked for
+enum PasswordPrompt {
+    /// Signing in as the user with this name
+    SignIn(String),
+    /// The signed in user's current password, before changing it
+    Current,
+    /// The new password, empty to remove it
+    New { current: Option<String> },
+    /// The new password once more, to catch typos
+    Confirm {
+        current: Option<String>,
+        new: String,
+    },
+}
+
+/// What is shown below the input box
+#[derive(PartialEq, Clone, Copy)]
+enum View {
+    Messages,
+    Dashboard,
+    /// The heatmap of this year
+    Heatmap(i32),
+}
+
+/// Decides when to prompt for a note about the work phase that just ended. The prompt waits
+/// while something else is being typed, so it never takes over the input box.
+pub struct ReflectionTrigger {
+    /// Timer state seen at the last update, used to notice phase changes
+    last_state: TimerState,
+    /// A work phase ended while the prompt could not be shown
+    pending: bool,
+}
+
+impl ReflectionTrigger {
+    pub fn new() -> Self {
+        ReflectionTrigger {
+            last_state: TimerState::Idle,
+            pending: false,
+        }
+    }
+
+    /// Follow the timer's state and tell whether to prompt for a note now. `enabled` is whether
+    /// the user wants the prompt and `can_prompt` whether the input box is free for it.
+    pub fn update(&mut self, state: TimerState, enabled: bool, can_prompt: bool) -> bool {
+        if enabled && self.last_state == TimerState::Working && state == TimerState::Breaking {
+            self.pending = true;
+        }
+        self.last_state = state;
+
+        let prompt = self.pending && can_prompt;
+        if prompt {
+            self.pending = false;
+        }
+        prompt
+    }
+}
+
+impl Default for ReflectionTrigger {
+    fn default() -> Self {
+        ReflectionTrigger::new()
+    }
 }
 
 #[derive(PartialEq)]
@@ -47,13 +141,17 @@ enum AppEvent {
 }
 
 impl App {
-    pub fn new(timer: PomodoroTimer) -> Self {
+    pub fn new(timer: PomodoroTimer, keymap: Keymap) -> Self {
         App {
             input: "".into(),
             input_mode: InputMode::Normal,
             messages: vec![],
             timer,
             prev_message: 0,
+            reflection: ReflectionTrigger::new(),
+            view: View::Messages,
+            keymap,
+            show_help: false,
         }
     }
     pub fn run(mut self, mut terminal: DefaultTerminal) {
@@ -68,6 +166,8 @@ impl App {
         Self::spawn_read_thread(input_tx);
 
         loop {
+            self.check_phase_change();
+
             terminal
                 .draw(|frame| self.draw(frame))
                 .expect("Could not draw");
@@ -75,15 +175,17 @@ impl App {
             match rx.recv() {
                 Ok(AppEvent::Tick) => {}
                 Ok(AppEvent::Key(key)) => match self.input_mode {
-                    InputMode::Normal => match key.code {
-                        KeyCode::Char('e') => {
-                            self.input_mode = InputMode::Editing;
-                        }
-                        KeyCode::Char('q') => {
-                            return;
+                    // Any key closes the help overlay
+                    InputMode::Normal if key.kind == KeyEventKind::Press && self.show_help => {
+                        self.show_help = false;
+                    }
+                    InputMode::Normal if key.kind == KeyEventKind::Press => {
+                        match self.keymap.action(&key) {
+                            Some(Action::Quit) => return,
+                            Some(action) => self.perform(action),
+                            None => {}
                         }
-                        _ => {}
-                    },
+                    }
                     InputMode::Editing if key.kind == KeyEventKind::Press => match key.code {
                         KeyCode::Enter => self.submit_command(),
                         KeyCode::Char(to_insert) => self.enter_char(to_insert),
@@ -96,7 +198,34 @@ impl App {
                         KeyCode::Esc => self.input_mode = InputMode::Normal,
                         _ => {}
                     },
-                    InputMode::Editing => {}
+                    InputMode::Reflection if key.kind == KeyEventKind::Press => match key.code {
+                        KeyCode::Enter => self.submit_reflection(),
+                        KeyCode::Char(to_insert) => self.enter_char(to_insert),
+                        KeyCode::Backspace => self.delete_char(),
+                        KeyCode::Left => self.move_cursor_left(),
+                        KeyCode::Right => self.move_cursor_right(),
+                        KeyCode::Esc => {
+                            self.input = "".into();
+                            self.input_mode = InputMode::Normal;
+                        }
+                        _ => {}
+                    },
+                    InputMode::Password(_) if key.kind == KeyEventKind::Press => match key.code {
+                        KeyCode::Enter => self.submit_password(),
+                        KeyCode::Char(to_insert) => self.enter_char(to_insert),
+                        KeyCode::Backspace => self.delete_char(),
+                        KeyCode::Left => self.move_cursor_left(),
+                        KeyCode::Right => self.move_cursor_right(),
+                        KeyCode::Esc => {
+                            self.input = "".into();
+                            self.input_mode = InputMode::Normal;
+                        }
+                        _ => {}
+                    },
+                    InputMode::Normal
+                    | InputMode::Editing
+                    | InputMode::Reflection
+                    | InputMode::Password(_) => {}
                 },
                 Err(_) => {
                     panic!("Main thread threw error when receiving event.")
@@ -139,7 +268,7 @@ impl App {
 
     fn draw(&mut self, frame: &mut Frame) {
         let vertical = Layout::vertical([
-            Constraint::Length(8),
+            Constraint::Percentage(40),
             Constraint::Length(2),
             Constraint::Length(3),
             Constraint::Min(1),
@@ -159,14 +288,52 @@ impl App {
         let break_duration_min = break_duration / 60;
         let break_duration_sec = break_duration % 60;
 
-        let timer_text = vec![
-            text::Line::from(format!(
-                "Time remaining: {:02}:{:02}",
-                time_remaining_min, time_remaining_sec
+        let state = self.timer.get_state();
+        let timer_block = Block::bordered()
+            .title("Timer")
+            .style(Style::default().fg(Color::Green));
+        let [countdown_area, gauge_area, tomatoes_area, status_area] = Layout::vertical([
+            Constraint::Min(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(2),
+        ])
+        .areas(timer_block.inner(information_area));
+        frame.render_widget(timer_block, information_area);
+
+        let countdown = format!("{:02}:{:02}", time_remaining_min, time_remaining_sec);
+        frame.render_widget(
+            BigDigits::new(&countdown).style(Style::default().fg(App::phase_color(state))),
+            countdown_area,
+        );
+
+        let ratio = App::phase_elapsed_ratio(
+            state,
+            Duration::from_secs(time_remaining),
+            self.timer.get_phase_duration(),
+        );
+        let gauge = LineGauge::default()
+            .filled_style(Style::default().fg(App::phase_color(state)))
+            .unfilled_style(Style::default().fg(Color::DarkGray))
+            .line_set(symbols::line::THICK)
+            .ratio(ratio)
+            .label(format!("{:>3.0}%", ratio * 100.0));
+        frame.render_widget(gauge, gauge_area);
+
+        frame.render_widget(
+            Paragraph::new(App::tomato_line(
+                self.timer.get_cycle_progress(),
+                self.timer.get_pomodoros_today(),
             )),
-            text::Line::from(""),
-            text::Line::from(format!("Timer state: {:?}", self.timer.get_state())),
-            text::Line::from(""),
+            tomatoes_area,
+        );
+
+        let timer_text = vec![
+            text::Line::from(if self.timer.is_paused() {
+                format!("Timer state: {:?} (paused)", state)
+            } else {
+                format!("Timer state: {:?}", state)
+            }),
             text::Line::from(format!(
                 "Current stats: Working {:02}:{:02} and breaking {:02}:{:02}",
                 work_duration_min, work_duration_sec, break_duration_min, break_duration_sec
@@ -174,20 +341,21 @@ impl App {
         ];
 
         let timer_widget = Paragraph::new(timer_text)
-            .block(Block::bordered().title("Timer"))
             .style(Style::default().fg(Color::Green))
             .wrap(Wrap { trim: false });
 
-        frame.render_widget(timer_widget, information_area);
+        frame.render_widget(timer_widget, status_area);
 
         let (msg, style) = match self.input_mode {
             InputMode::Normal => (
                 vec![
                     "Press ".into(),
-                    "q".bold(),
+                    self.keymap.keys(Action::Quit).bold(),
                     " to exit, ".into(),
-                    "e".bold(),
-                    " to start entering commands.".bold(),
+                    self.keymap.keys(Action::EnterCommand).bold(),
+                    " to start entering commands, ".bold(),
+                    self.keymap.keys(Action::Help).bold(),
+                    " for all keys.".into(),
                 ],
                 Style::default().add_modifier(Modifier::RAPID_BLINK),
             ),
@@ -201,36 +369,121 @@ impl App {
                 ],
                 Style::default(),
             ),
+            InputMode::Reflection => (
+                vec![
+                    "What did you get done? ".bold(),
+                    "Enter".bold(),
+                    " to save it as a note, ".into(),
+                    "Esc".bold(),
+                    " to skip".into(),
+                ],
+                Style::default(),
+            ),
+            InputMode::Password(ref prompt) => (
+                vec![
+                    match prompt {
+                        PasswordPrompt::SignIn(username) => format!("Password for {}. ", username),
+                        PasswordPrompt::Current => String::from("Current password. "),
+                        PasswordPrompt::New { .. } => {
+                            String::from("New password, empty to remove it. ")
+                        }
+                        PasswordPrompt::Confirm { .. } => String::from("New password again. "),
+                    }
+                    .bold(),
+                    "Enter".bold(),
+                    " to confirm, ".into(),
+                    "Esc".bold(),
+                    " to cancel".into(),
+                ],
+                Style::default(),
+            ),
         };
 
         let text = Text::from(Line::from(msg)).patch_style(style);
         let help_message = Paragraph::new(text).wrap(Wrap { trim: true });
         frame.render_widget(help_message, help_area);
 
-        let input = Paragraph::new(self.input.to_string())
+        let input_text = match self.input_mode {
+            InputMode::Password(_) => "*".repeat(self.input.value().chars().count()),
+            _ => self.input.to_string(),
+        };
+        let input = Paragraph::new(input_text)
             .style(match self.input_mode {
                 InputMode::Normal => Style::default(),
                 InputMode::Editing => Style::default().fg(Color::Yellow),
+                InputMode::Reflection => Style::default().fg(Color::Cyan),
+                InputMode::Password(_) => Style::default().fg(Color::Magenta),
             })
-            .block(Block::bordered().title("Input"));
+            .block(Block::bordered().title(match self.input_mode {
+                InputMode::Reflection => "Note",
+                InputMode::Password(_) => "Password",
+                _ => "Input",
+            }));
         frame.render_widget(input, input_area);
 
         match self.input_mode {
             InputMode::Normal => {}
-            InputMode::Editing => frame.set_cursor_position(Position::new(
-                input_area.x + self.input.cursor() as u16 + 1,
-                input_area.y + 1,
-            )),
+            InputMode::Editing | InputMode::Reflection | InputMode::Password(_) => frame
+                .set_cursor_position(Position::new(
+                    input_area.x + self.input.cursor() as u16 + 1,
+                    input_area.y + 1,
+                )),
+        }
+
+        match self.view {
+            View::Messages => self.draw_messages(frame, messages_area),
+            View::Dashboard => self.draw_dashboard(frame, messages_area),
+            View::Heatmap(year) => self.draw_heatmap(frame, messages_area, year),
+        }
+
+        if self.show_help {
+            self.draw_help(frame);
         }
+    }
 
+    /// The keys of every action, from the active keymap, over the middle of the screen
+    fn draw_help(&self, frame: &mut Frame) {
+        let help = self.keymap.help();
+        let keys_width = help.iter().map(|(keys, _)| keys.chars().count()).max();
+        let lines: Vec<Line> = help
+            .into_iter()
+            .map(|(keys, description)| {
+                Line::from(vec![
+                    format!("{:>width$}  ", keys, width = keys_width.unwrap_or(0)).bold(),
+                    description.into(),
+                ])
+            })
+            .collect();
+
+        let area = frame.area();
+        let width = (lines.iter().map(Line::width).max().unwrap_or(0) as u16 + 4).min(area.width);
+        let height = (lines.len() as u16 + 2).min(area.height);
+        let popup = Rect::new(
+            area.x + (area.width - width) / 2,
+            area.y + (area.height - height) / 2,
+            width,
+            height,
+        );
+
+        frame.render_widget(Clear, popup);
+        frame.render_widget(
+            Paragraph::new(lines).block(
+                Block::bordered()
+                    .title("Keys, any key to close")
+                    .style(Style::default().fg(Color::Cyan)),
+            ),
+            popup,
+        );
+    }
+
+    fn draw_messages(&self, frame: &mut Frame, area: Rect) {
         let messages: Vec<ListItem> = self
             .messages
             .iter()
             .rev()
-            .enumerate()
-            .map(|(_, m)| {
+            .map(|m| {
                 let (message, t) = m;
-                let content = Line::from(Span::raw(format!("{message}")));
+                let content = Text::raw(message.clone());
                 let color = match t {
                     ValidCommand => Color::Green,
                     InvalidCommand => Color::Red,
@@ -240,27 +493,132 @@ impl App {
             })
             .collect();
         let messages = List::new(messages).block(Block::bordered().title("Messages"));
-        frame.render_widget(messages, messages_area);
+        frame.render_widget(messages, area);
+    }
+
+    /// Do what a key outside of the input box is bound to
+    fn perform(&mut self, action: Action) {
+        match action {
+            Action::EnterCommand => self.input_mode = InputMode::Editing,
+            Action::StartPause => {
+                let state = self.timer.get_state();
+                if state == TimerState::Idle || self.timer.is_paused() {
+                    let reply = self.start_session(Vec::new()).unwrap_or_else(Some);
+                    self.inform(reply);
+                } else {
+                    self.timer.pause_timer();
+                }
+            }
+            Action::Stop => self.timer.stop_timer(),
+            Action::Skip => {
+                let result = self.timer.skip_phase();
+                self.inform(result.err());
+            }
+            Action::Extend => {
+                let result = self
+                    .timer
+                    .extend_phase(Duration::from_secs(KEY_ADJUST_MINUTES * 60));
+                self.inform(result.err());
+            }
+            Action::Shorten => {
+                let result = self
+                    .timer
+                    .shorten_phase(Duration::from_secs(KEY_ADJUST_MINUTES * 60));
+                self.inform(result.err());
+            }
+            Action::Dashboard => self.toggle_view(View::Dashboard),
+            Action::Heatmap => {
+                let year = self.timer.get_user_settings().today().year();
+                self.toggle_view(View::Heatmap(year))
+            }
+            Action::PreviousYear | Action::NextYear => {
+                if let View::Heatmap(year) = self.view {
+                    let step = if action == Action::PreviousYear {
+                        -1
+                    } else {
+                        1
+                    };
+                    self.view = View::Heatmap(year + step);
+                }
+            }
+            Action::Help => self.show_help = !self.show_help,
+            // Ends `run`
+            Action::Quit => {}
+        }
+    }
+
+    /// Show the reply to a key, keys are not recorded like typed commands
+    fn inform(&mut self, reply: Option<String>) {
+        if let Some(reply) = reply {
+            self.messages.push((reply, MessageType::Information));
+        }
+    }
+
+    /// Start a new session with the tags, or resume the paused one. Returns a warning when
+    /// the task is already over its estimate.
+    fn start_session(&mut self, tags: Vec<String>) -> Result<Option<String>, String> {
+        if !self.timer.is_user_signed_in() {
+            return Err(String::from(
+                "You have to login with a user before you can start a session",
+            ));
+        }
+
+        let mut warning = None;
+        // Tags only apply to new sessions, not when resuming a paused one
+        if self.timer.get_state() == TimerState::Idle {
+            self.timer.set_tags(tags);
+
+            // Flag when this pomodoro goes beyond the task's estimate
+            if let Some((estimate, actual)) = self.timer.get_task_progress() {
+                if actual >= estimate as i64 {
+                    warning = Some(format!(
+                        "Task {:?} is over its estimate: {} of {} pomodoros already done",
+                        self.timer.get_task_name().unwrap_or_default(),
+                        actual,
+                        estimate
+                    ));
+                }
+            }
+        }
+        self.timer.start_timer();
+        Ok(warning)
     }
 
     fn submit_command(&mut self) {
         let message = self.input.to_string();
+
+        // Clear the terminal
+        self.input = "".into();
+        self.prev_message = 0;
+
+        self.run_command(message);
+    }
+
+    /// Carry out a typed command and record it with its reply
+    fn run_command(&mut self, message: String) {
         let mut reply: Option<String> = None;
+        let mut password_prompt: Option<PasswordPrompt> = None;
         let message_array: Vec<&str> = message.split_whitespace().collect();
 
         let command_validity = match message_array.first() {
-            Some(&"start") => match self.timer.is_user_signed_in() {
-                true => {
-                    self.timer.start_timer();
-                    ValidCommand
-                }
-                _ => {
-                    reply = Some(String::from(
-                        "You have to login with a user before you can start a session",
-                    ));
-                    InvalidCommand
+            Some(&"start") => {
+                let tags = message_array[1..]
+                    .iter()
+                    .filter_map(|arg| arg.strip_prefix('#'))
+                    .filter(|tag| !tag.is_empty())
+                    .map(String::from)
+                    .collect();
+                match self.start_session(tags) {
+                    Ok(warning) => {
+                        reply = warning;
+                        ValidCommand
+                    }
+                    Err(e) => {
+                        reply = Some(e);
+                        InvalidCommand
+                    }
                 }
-            },
+            }
             Some(&"stop") => {
                 self.timer.stop_timer();
                 ValidCommand
@@ -269,10 +627,483 @@ impl App {
                 self.timer.pause_timer();
                 ValidCommand
             }
+            Some(&"skip") => match self.timer.skip_phase() {
+                Ok(()) => ValidCommand,
+                Err(e) => {
+                    reply = Some(e);
+                    InvalidCommand
+                }
+            },
+            Some(&command @ ("extend" | "shorten")) => {
+                let by = message_array
+                    .get(1)
+                    .and_then(|minutes| App::parse_phase_minutes(minutes));
+
+                let result = match by {
+                    None => Err(format!("Give the minutes to {} the phase by", command)),
+                    Some(by) if command == "extend" => self.timer.extend_phase(by),
+                    Some(by) => self.timer.shorten_phase(by),
+                };
+                match result {
+                    Ok(()) => ValidCommand,
+                    Err(e) => {
+                        reply = Some(e);
+                        InvalidCommand
+                    }
+                }
+            }
             Some(&"help") => {
-                reply = Some(String::from("Commands: Start, Stop, Pause, Set <state> <duration in min>, stats <today, all-time>, login <user-name>, whoami, users"));
+                reply = Some(String::from("Commands: Start [#tag ...], Stop, Pause, Skip, Extend <min>, Shorten <min>, Set <state> <duration in min>, stats <today, yesterday, week, last-week, month, year, all-time, <n>d, <from>..<to>> [#tag], note <text>, set reflection <on, off>, set auto-start <on, off>, set notifications <on, off>, set long-break <min> <every n pomodoros>, set long-break off, set week-start <day>, set day-start <hour>, set timezone <zone, local>, task <name, none> [estimate], tasks, estimates, export <file.csv, file.json> [from] [to], import <file.csv, file.json> [dry-run], log [count], add <date> <work> <break>, edit <id> <date, work, break, note, user> <value>, delete <id>, undo, backup <file>, restore <file>, merge <other.db>, settings, login <user-name>, passwd, set ignore-case <on, off> (until you quit), user rename <old> <new>, user merge <from> <into>, whoami, users"));
+                ValidCommand
+            }
+            Some(&"set") if message_array.get(1) == Some(&"reflection") => {
+                let result = match App::parse_switch(message_array.get(2)) {
+                    Some(on) => self.timer.set_reflection_prompt(on),
+                    None => Err(String::from("Reflection prompt can only be on or off")),
+                };
+                App::settings_reply(result, &mut reply)
+            }
+            Some(&"set") if message_array.get(1) == Some(&"auto-start") => {
+                let result = match App::parse_switch(message_array.get(2)) {
+                    Some(on) => self.timer.set_auto_start(on),
+                    None => Err(String::from("Auto start can only be on or off")),
+                };
+                App::settings_reply(result, &mut reply)
+            }
+            Some(&"set") if message_array.get(1) == Some(&"notifications") => {
+                let result = match App::parse_switch(message_array.get(2)) {
+                    Some(on) => self.timer.set_notifications(on),
+                    None => Err(String::from("Notifications can only be on or off")),
+                };
+                App::settings_reply(result, &mut reply)
+            }
+            Some(&"set") if message_array.get(1) == Some(&"long-break") => {
+                let minutes = message_array.get(2).map(|min| min.parse::<f32>());
+                let every = message_array.get(3).map(|every| every.parse::<u32>());
+                let result = match (message_array.get(2), minutes, every) {
+                    (Some(&"off"), _, None) => {
+                        let duration = Duration::from_secs(
+                            self.timer.get_user_settings().long_break_duration_secs,
+                        );
+                        self.timer.set_long_break(duration, 0)
+                    }
+                    (_, Some(Ok(minutes)), Some(Ok(every))) if minutes >= 0.0 => {
+                        self.timer.set_long_break(
+                            Duration::from_secs((minutes * 60.0).floor() as u64),
+                            every,
+                        )
+                    }
+                    _ => Err(String::from(
+                        "Write the long break in minutes and every how many pomodoros it is, \
+                         like 15 4, or off",
+                    )),
+                };
+                App::settings_reply(result, &mut reply)
+            }
+            Some(&"set") if message_array.get(1) == Some(&"day-start") => {
+                let result = match message_array.get(2).map(|hour| hour.parse::<u32>()) {
+                    Some(Ok(hour)) => self.timer.set_day_start_hour(hour),
+                    _ => Err(String::from("Write the hour the day starts at, like 4")),
+                };
+                App::settings_reply(result, &mut reply)
+            }
+            Some(&"set") if message_array.get(1) == Some(&"timezone") => {
+                let result = match message_array.get(2) {
+                    Some(&"local") => self.timer.set_time_zone(None),
+                    Some(time_zone) => self.timer.set_time_zone(Some(time_zone)),
+                    None => Err(String::from(
+                        "Write a time zone like Europe/Copenhagen, or local",
+                    )),
+                };
+                App::settings_reply(result, &mut reply)
+            }
+            Some(&"set") if message_array.get(1) == Some(&"ignore-case") => {
+                match message_array.get(2) {
+                    Some(&"on") => {
+                        self.timer.set_ignore_name_case(true);
+                        ValidCommand
+                    }
+                    Some(&"off") => {
+                        self.timer.set_ignore_name_case(false);
+                        ValidCommand
+                    }
+                    _ => {
+                        reply = Some(String::from("Ignoring case can only be on or off"));
+                        InvalidCommand
+                    }
+                }
+            }
+            Some(&"set") if message_array.get(1) == Some(&"week-start") => {
+                match message_array.get(2).map(|day| day.parse::<Weekday>()) {
+                    Some(Ok(week_start)) => {
+                        self.timer.set_week_start(week_start);
+                        ValidCommand
+                    }
+                    _ => {
+                        reply = Some(String::from("Weeks can start on monday to sunday"));
+                        InvalidCommand
+                    }
+                }
+            }
+            Some(&"note") => {
+                let text = message_array[1..].join(" ");
+                if text.is_empty() {
+                    reply = Some(String::from("Write the note after the command"));
+                    InvalidCommand
+                } else if self.timer.set_note(&text) {
+                    reply = Some(String::from("Note saved"));
+                    ValidCommand
+                } else {
+                    reply = Some(String::from("There is no session to attach the note to"));
+                    InvalidCommand
+                }
+            }
+            Some(&"task") => {
+                let args = &message_array[1..];
+
+                // A trailing number is the estimate
+                let (name, estimate) = match args {
+                    [name @ .., estimate] if !name.is_empty() => match estimate.parse::<i32>() {
+                        Ok(estimate) => (name.join(" "), Some(estimate)),
+                        Err(_) => (args.join(" "), None),
+                    },
+                    _ => (args.join(" "), None),
+                };
+
+                if name.is_empty() {
+                    reply = Some(match self.timer.get_task_progress() {
+                        Some((estimate, actual)) => format!(
+                            "Working on {:?}: {} of {} pomodoros done",
+                            self.timer.get_task_name().unwrap_or_default(),
+                            actual,
+                            estimate
+                        ),
+                        None => String::from("You are not working on a task"),
+                    });
+                    ValidCommand
+                } else if name == "none" {
+                    self.timer.clear_task();
+                    ValidCommand
+                } else if estimate.is_some_and(|estimate| estimate < 1) {
+                    reply = Some(String::from("The estimate has to be at least 1 pomodoro"));
+                    InvalidCommand
+                } else if self.timer.set_task(&name, estimate) {
+                    ValidCommand
+                } else {
+                    reply = Some(String::from(
+                        "You have to login with a user before you can pick a task",
+                    ));
+                    InvalidCommand
+                }
+            }
+            Some(&"tasks") => {
+                let tasks = self.timer.get_tasks();
+                reply = Some(if tasks.is_empty() {
+                    String::from("You have no tasks")
+                } else {
+                    tasks
+                        .iter()
+                        .map(|(task, actual)| {
+                            format!(
+                                "{}: {} of {} pomodoros{}",
+                                task.name,
+                                actual,
+                                task.estimated_pomodoros,
+                                if *actual > task.estimated_pomodoros as i64 {
+                                    " (over estimate)"
+                                } else {
+                                    ""
+                                }
+                            )
+                        })
+                        .collect::<Vec<String>>()
+                        .join("\n")
+                });
+                ValidCommand
+            }
+            Some(&"estimates") => {
+                let accuracy = self.timer.get_estimate_accuracy();
+                reply = Some(if accuracy.is_empty() {
+                    String::from("No pomodoros have been completed on tasks yet")
+                } else {
+                    accuracy
+                        .iter()
+                        .map(|month| {
+                            format!(
+                                "{}: {} tasks took {:.2}x their estimate on average, {} within estimate",
+                                month.month, month.tasks, month.average_ratio, month.within_estimate
+                            )
+                        })
+                        .collect::<Vec<String>>()
+                        .join("\n")
+                });
                 ValidCommand
             }
+            Some(&"export") => {
+                let path = message_array.get(1).map(Path::new);
+                let format = path.and_then(ExportFormat::from_path);
+                let dates: Vec<Option<NaiveDate>> = message_array
+                    .iter()
+                    .skip(2)
+                    .map(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+                    .collect();
+
+                let date_range = match dates[..] {
+                    [] => Ok(None),
+                    [Some(from)] => Ok(Some((from, NaiveDate::MAX))),
+                    [Some(from), Some(to)] => Ok(Some((from, to))),
+                    _ => Err(()),
+                };
+
+                match (path, format, date_range) {
+                    (Some(path), Some(format), Ok(date_range)) => {
+                        match self.timer.export_runs(path, format, date_range) {
+                            Ok(count) => {
+                                reply =
+                                    Some(format!("Exported {} runs to {}", count, path.display()));
+                                ValidCommand
+                            }
+                            Err(e) => {
+                                reply = Some(format!("Could not export: {}", e));
+                                InvalidCommand
+                            }
+                        }
+                    }
+                    (_, _, Err(_)) => {
+                        reply = Some(String::from("Dates have to be written as YYYY-MM-DD"));
+                        InvalidCommand
+                    }
+                    _ => {
+                        reply = Some(String::from("Export to a file ending in .csv or .json"));
+                        InvalidCommand
+                    }
+                }
+            }
+            Some(&"import") => {
+                let path = message_array.get(1).map(Path::new);
+                let format = path.and_then(ExportFormat::from_path);
+                let dry_run = message_array.get(2) == Some(&"dry-run");
+
+                match (path, format) {
+                    (Some(path), Some(format)) => {
+                        match self.timer.import_runs(path, format, dry_run) {
+                            Ok(report) => {
+                                let mut lines = vec![format!(
+                                    "{} {} runs, skipped {} duplicates, {} rows with errors",
+                                    if dry_run { "Would import" } else { "Imported" },
+                                    report.imported,
+                                    report.duplicates,
+                                    report.errors.len()
+                                )];
+                                lines.extend(
+                                    report
+                                        .errors
+                                        .iter()
+                                        .take(MAX_IMPORT_ERRORS)
+                                        .map(|(row, e)| format!("  Row {}: {}", row, e)),
+                                );
+                                if report.errors.len() > MAX_IMPORT_ERRORS {
+                                    lines.push(format!(
+                                        "  ... and {} more",
+                                        report.errors.len() - MAX_IMPORT_ERRORS
+                                    ));
+                                }
+                                reply = Some(lines.join("\n"));
+                                ValidCommand
+                            }
+                            Err(e) => {
+                                reply = Some(format!("Could not import: {}", e));
+                                InvalidCommand
+                            }
+                        }
+                    }
+                    _ => {
+                        reply = Some(String::from("Import from a file ending in .csv or .json"));
+                        InvalidCommand
+                    }
+                }
+            }
+            Some(&"log") => {
+                let count = match message_array.get(1) {
+                    Some(count) => count.parse::<usize>().ok(),
+                    None => Some(LOG_LENGTH),
+                };
+
+                match count {
+                    Some(_) if !self.timer.is_user_signed_in() => {
+                        reply = Some(String::from(
+                            "You have to login with a user before you can see your runs",
+                        ));
+                        InvalidCommand
+                    }
+                    Some(count) => {
+                        let runs = self.timer.get_recent_runs(count);
+                        reply = Some(if runs.is_empty() {
+                            String::from("You have no runs yet")
+                        } else {
+                            runs.iter()
+                                .map(App::format_run)
+                                .collect::<Vec<String>>()
+                                .join("\n")
+                        });
+                        ValidCommand
+                    }
+                    None => {
+                        reply = Some(String::from("Write the number of runs to show after log"));
+                        InvalidCommand
+                    }
+                }
+            }
+            Some(&"add") => {
+                let date = message_array
+                    .get(1)
+                    .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok());
+                let secs: Vec<Option<i32>> = message_array
+                    .iter()
+                    .skip(2)
+                    .map(|minutes| App::parse_minutes(minutes))
+                    .collect();
+
+                match (date, &secs[..]) {
+                    (Some(date), [Some(working), Some(breaking)]) => {
+                        match self.timer.add_run(date, *working, *breaking) {
+                            Some(timer_run_id) => {
+                                reply = Some(format!("Added run #{}", timer_run_id));
+                                ValidCommand
+                            }
+                            None => {
+                                reply = Some(String::from(
+                                    "You have to login with a user before you can add runs",
+                                ));
+                                InvalidCommand
+                            }
+                        }
+                    }
+                    _ => {
+                        reply = Some(String::from(
+                            "Add a run with add <YYYY-MM-DD> <work in min> <break in min>",
+                        ));
+                        InvalidCommand
+                    }
+                }
+            }
+            Some(&"edit") => {
+                let timer_run_id = message_array.get(1).and_then(|id| id.parse::<i32>().ok());
+                let value = message_array.get(3..).map(|words| words.join(" "));
+                let secs = value.as_deref().and_then(App::parse_minutes);
+
+                let edit = match (message_array.get(2), value) {
+                    (Some(&"date"), Some(date)) => NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+                        .ok()
+                        .map(RunEdit::Date),
+                    (Some(&"work"), _) => secs.map(RunEdit::WorkingTime),
+                    (Some(&"break"), _) => secs.map(RunEdit::BreakingTime),
+                    (Some(&"note"), Some(note)) if !note.is_empty() => Some(RunEdit::Note(note)),
+                    (Some(&"user"), Some(username)) if !username.is_empty() => {
+                        Some(RunEdit::User(username))
+                    }
+                    _ => None,
+                };
+
+                match (timer_run_id, edit) {
+                    (Some(timer_run_id), Some(edit)) => {
+                        match self.timer.edit_run(timer_run_id, edit) {
+                            Ok(()) => {
+                                reply = Some(format!("Changed run #{}", timer_run_id));
+                                ValidCommand
+                            }
+                            Err(e) => {
+                                reply = Some(e);
+                                InvalidCommand
+                            }
+                        }
+                    }
+                    _ => {
+                        reply = Some(String::from(
+                            "Edit a run with edit <id> <date, work, break, note, user> <value>",
+                        ));
+                        InvalidCommand
+                    }
+                }
+            }
+            Some(&"delete") => match message_array.get(1).and_then(|id| id.parse::<i32>().ok()) {
+                Some(timer_run_id) if self.timer.delete_run(timer_run_id) => {
+                    reply = Some(format!(
+                        "Deleted run #{}, undo brings it back",
+                        timer_run_id
+                    ));
+                    ValidCommand
+                }
+                Some(timer_run_id) => {
+                    reply = Some(format!("You have no run #{}", timer_run_id));
+                    InvalidCommand
+                }
+                None => {
+                    reply = Some(String::from("Write the id of the run to delete, see log"));
+                    InvalidCommand
+                }
+            },
+            Some(&"undo") => match self.timer.undo() {
+                Some(timer_run_id) => {
+                    reply = Some(format!("Undid the last change to run #{}", timer_run_id));
+                    ValidCommand
+                }
+                None => {
+                    reply = Some(String::from("There is nothing to undo"));
+                    InvalidCommand
+                }
+            },
+            Some(&"backup") => match message_array.get(1).map(Path::new) {
+                Some(path) => match self.timer.backup(path) {
+                    Ok(()) => {
+                        reply = Some(format!("Backed up to {}", path.display()));
+                        ValidCommand
+                    }
+                    Err(e) => {
+                        reply = Some(format!("Could not back up: {}", e));
+                        InvalidCommand
+                    }
+                },
+                None => {
+                    reply = Some(String::from("Write the backup file after the command"));
+                    InvalidCommand
+                }
+            },
+            Some(&"restore") => match message_array.get(1).map(Path::new) {
+                Some(path) => match self.timer.restore(path) {
+                    Ok(()) => {
+                        reply = Some(format!("Restored {}", path.display()));
+                        ValidCommand
+                    }
+                    Err(e) => {
+                        reply = Some(format!("Could not restore: {}", e));
+                        InvalidCommand
+                    }
+                },
+                None => {
+                    reply = Some(String::from("Write the backup file after the command"));
+                    InvalidCommand
+                }
+            },
+            Some(&"merge") => match message_array.get(1).map(Path::new) {
+                Some(path) => match self.timer.merge_database(path) {
+                    Ok(report) => {
+                        reply = Some(format!(
+                            "Merged {} runs, skipped {} already known",
+                            report.imported, report.duplicates
+                        ));
+                        ValidCommand
+                    }
+                    Err(e) => {
+                        reply = Some(format!("Could not merge: {}", e));
+                        InvalidCommand
+                    }
+                },
+                None => {
+                    reply = Some(String::from("Write the database file after the command"));
+                    InvalidCommand
+                }
+            },
             Some(&"set") => {
                 let mut command_validity = ValidCommand;
                 let state_to_update = match message_array.get(1) {
@@ -302,61 +1133,72 @@ impl App {
                     None => command_validity = InvalidCommand,
                 }
 
-                if new_time_amount.is_some() && state_to_update.is_some() {
-                    let time_in_min = new_time_amount.unwrap() * 60.0;
+                if let (Some(new_time_amount), Some(state_to_update)) =
+                    (new_time_amount, state_to_update)
+                {
+                    let time_in_min = new_time_amount * 60.0;
                     let period = Duration::from_secs(time_in_min.floor() as u64);
-                    self.timer
-                        .set_state_time_period(period, state_to_update.unwrap())
+                    self.timer.set_state_time_period(period, state_to_update)
                 }
 
                 command_validity
             }
-            Some(&"stats") => {
-                let when = message_array.get(1);
+            Some(&"stats") => match message_array.get(1).and_then(|arg| App::parse_period(arg)) {
+                Some(period) => {
+                    let tag = message_array.get(2).and_then(|arg| arg.strip_prefix('#'));
 
-                let (work_dur, break_dur) = match when {
-                    Some(&"today") => self.timer.get_total_time(Today),
-                    Some(&"all-time") => self.timer.get_total_time(AllTime),
-                    _ => (-1, -1),
-                };
+                    let (work_dur, break_dur) = match tag {
+                        Some(tag) => self.timer.get_total_time_for_tag(period, tag),
+                        None => self.timer.get_total_time(period),
+                    };
 
-                // Get number in minutes
-                let work_dur_min = work_dur as f64 / 60.0;
-                let break_dur_min = break_dur as f64 / 60.;
-                let mut work_dur_str = format!("{:.2} minutes", work_dur_min);
-                let mut break_dur_str = format!("{:.2} minutes", break_dur_min);
+                    let mut lines = vec![format!(
+                        "{}{}: Total work duration: {}. Total break duration: {}",
+                        self.timer.get_username().unwrap_or("None".to_string()),
+                        tag.map(|tag| format!(" #{}", tag)).unwrap_or_default(),
+                        App::format_duration(work_dur),
+                        App::format_duration(break_dur)
+                    )];
 
-                // When have worked over an hour get it in hours
-                if work_dur_min > 60.0 {
-                    let work_dur_hrs = work_dur_min / 60.0;
-                    work_dur_str = format!("{:.2} hours", work_dur_hrs);
-                }
+                    // Show how the time is split between tags
+                    if tag.is_none() {
+                        for (tag_name, work_dur, break_dur) in self.timer.get_tag_breakdown(period)
+                        {
+                            lines.push(format!(
+                                "  #{}: work {}, break {}",
+                                tag_name,
+                                App::format_duration(work_dur),
+                                App::format_duration(break_dur)
+                            ));
+                        }
+                    }
 
-                if break_dur_min > 60.0 {
-                    let break_dur_hrs = break_dur_min / 60.0;
-                    break_dur_str = format!("{:.2} hours", break_dur_hrs);
+                    reply = Some(lines.join("\n"));
+                    ValidCommand
                 }
-
-                reply = Some(format!(
-                    "{}: Total work duration: {}. Total break duration: {}",
-                    self.timer.get_username().unwrap_or("None".to_string()),
-                    work_dur_str,
-                    break_dur_str
-                ));
+                None => {
+                    reply = Some(String::from(
+                        "Pick a period: today, yesterday, week, last-week, month, year, all-time, \
+                         <n>d or <from>..<to>",
+                    ));
+                    InvalidCommand
+                }
+            },
+            Some(&"settings") => {
+                reply = Some(self.format_settings());
                 ValidCommand
             }
             Some(&"login") => {
                 let username = message_array.get(1);
 
                 match username {
-                    Some(username) => {
-                        let success = self.timer.sign_in(username);
-                        if success {
-                            reply = Some(String::from("You are signed in!"))
-                        } else {
-                            reply = Some(String::from("Something went wrong trying to sign you in"))
+                    Some(username) => match self.timer.sign_in(username, None) {
+                        Ok(()) => reply = Some(String::from("You are signed in!")),
+                        Err(SignInError::PasswordRequired) => {
+                            password_prompt = Some(PasswordPrompt::SignIn(username.to_string()))
                         }
-                    }
+                        Err(e) => reply = Some(format!("Could not sign you in: {}", e)),
+                    },
                     None => {
                         reply = Some(String::from("Enter a username please"));
                     }
@@ -364,6 +1206,45 @@ impl App {
 
                 ValidCommand
             }
+            Some(&"passwd") => {
+                if !self.timer.is_user_signed_in() {
+                    reply = Some(String::from("Sign in first"));
+                    InvalidCommand
+                } else {
+                    password_prompt = Some(if self.timer.has_password() {
+                        PasswordPrompt::Current
+                    } else {
+                        PasswordPrompt::New { current: None }
+                    });
+                    ValidCommand
+                }
+            }
+            Some(&"user") => {
+                let result = match message_array[1..] {
+                    ["rename", old_name, new_name] => self
+                        .timer
+                        .rename_user(old_name, new_name)
+                        .map(|()| format!("Renamed {} to {}", old_name, new_name)),
+                    ["merge", from_name, into_name] => self
+                        .timer
+                        .merge_users(from_name, into_name)
+                        .map(|()| format!("Moved everything of {} to {}", from_name, into_name)),
+                    _ => Err(String::from(
+                        "Change users with user rename <old> <new> or user merge <from> <into>",
+                    )),
+                };
+
+                match result {
+                    Ok(message) => {
+                        reply = Some(message);
+                        ValidCommand
+                    }
+                    Err(e) => {
+                        reply = Some(e);
+                        InvalidCommand
+                    }
+                }
+            }
             Some(&"whoami") => {
                 let username = self.timer.get_username();
                 reply = match username {
@@ -382,14 +1263,94 @@ impl App {
 
         self.messages.push((message, command_validity));
 
-        if reply.is_some() {
-            self.messages
-                .push((reply.unwrap(), MessageType::Information))
+        if let Some(reply) = reply {
+            self.messages.push((reply, MessageType::Information))
         }
 
-        // Clear the terminal
+        if let Some(prompt) = password_prompt {
+            self.input_mode = InputMode::Password(prompt);
+        }
+    }
+
+    /// Act on an entered password, which is never kept in the messages
+    fn submit_password(&mut self) {
+        let password = self.input.to_string();
         self.input = "".into();
-        self.prev_message = 0;
+        let InputMode::Password(prompt) =
+            std::mem::replace(&mut self.input_mode, InputMode::Editing)
+        else {
+            return;
+        };
+
+        let reply = match prompt {
+            PasswordPrompt::SignIn(username) => {
+                match self.timer.sign_in(&username, Some(&password)) {
+                    Ok(()) => (String::from("You are signed in!"), MessageType::Information),
+                    Err(e) => (format!("Could not sign you in: {}", e), InvalidCommand),
+                }
+            }
+            PasswordPrompt::Current => {
+                self.input_mode = InputMode::Password(PasswordPrompt::New {
+                    current: Some(password),
+                });
+                return;
+            }
+            PasswordPrompt::New { current } => {
+                self.input_mode = InputMode::Password(PasswordPrompt::Confirm {
+                    current,
+                    new: password,
+                });
+                return;
+            }
+            PasswordPrompt::Confirm { new, .. } if new != password => (
+                String::from("The passwords do not match, the password is unchanged"),
+                InvalidCommand,
+            ),
+            PasswordPrompt::Confirm { current, new } => {
+                let new_password = Some(new.as_str()).filter(|new| !new.is_empty());
+                match self.timer.set_password(current.as_deref(), new_password) {
+                    Ok(()) if new_password.is_none() => {
+                        (String::from("Password removed"), MessageType::Information)
+                    }
+                    Ok(()) => (String::from("Password changed"), MessageType::Information),
+                    Err(e) => (
+                        format!("Could not change the password: {}", e),
+                        InvalidCommand,
+                    ),
+                }
+            }
+        };
+
+        self.messages.push(reply);
+    }
+
+    /// Prompt for a note when a work phase has ended, once nothing else is being typed
+    fn check_phase_change(&mut self) {
+        let enabled =
+            self.timer.get_user_settings().reflection_prompt && self.timer.is_user_signed_in();
+        let can_prompt = matches!(self.input_mode, InputMode::Normal);
+
+        if self
+            .reflection
+            .update(self.timer.get_state(), enabled, can_prompt)
+        {
+            self.input = "".into();
+            self.input_mode = InputMode::Reflection;
+        }
+    }
+
+    fn submit_reflection(&mut self) {
+        let note = self.input.to_string();
+
+        if !note.trim().is_empty() && self.timer.set_note(note.trim()) {
+            self.messages.push((
+                format!("Note saved: {}", note.trim()),
+                MessageType::Information,
+            ));
+        }
+
+        self.input = "".into();
+        self.input_mode = InputMode::Normal;
     }
 
     fn enter_char(&mut self, char_entered: char) {
@@ -436,12 +1397,284 @@ impl App {
         if idx == 0 {
             self.input = "".into();
         } else {
-            match prev_message.get(idx.saturating_sub(1)) {
-                Some(msg) => {
-                    self.input = msg.clone().into();
+            if let Some(msg) = prev_message.get(idx.saturating_sub(1)) {
+                self.input = msg.clone().into();
+            }
+        }
+    }
+
+    /// Read a stats period like `week`, `30d` or `2026-09-01..2026-09-30`
+    /// Seconds of a run's working or breaking time given in minutes, `None` when it is not a
+    /// number of minutes or too long to be stored
+    pub fn parse_minutes(arg: &str) -> Option<i32> {
+        arg.parse::<i32>()
+            .ok()
+            .filter(|minutes| *minutes >= 0)?
+            .checked_mul(60)
+    }
+
+    /// Time to extend or shorten a phase by, given in minutes, `None` when it is not a positive
+    /// number of minutes or too many to count in seconds
+    pub fn parse_phase_minutes(arg: &str) -> Option<Duration> {
+        arg.parse::<u64>()
+            .ok()
+            .filter(|minutes| *minutes > 0)?
+            .checked_mul(60)
+            .map(Duration::from_secs)
+    }
+
+    fn parse_period(arg: &str) -> Option<Period> {
+        match arg {
+            "today" => Some(Period::Today),
+            "yesterday" => Some(Period::Yesterday),
+            "week" => Some(Period::ThisWeek),
+            "last-week" => Some(Period::LastWeek),
+            "month" => Some(Period::ThisMonth),
+            "year" => Some(Period::ThisYear),
+            "all-time" => Some(Period::AllTime),
+            _ => {
+                if let Some((from, to)) = arg.split_once("..") {
+                    let from = NaiveDate::parse_from_str(from, "%Y-%m-%d").ok()?;
+                    let to = NaiveDate::parse_from_str(to, "%Y-%m-%d").ok()?;
+                    (from <= to).then_some(Period::Range(from, to))
+                } else {
+                    let days = arg.strip_suffix('d')?.parse::<u32>().ok()?;
+                    (days > 0).then_some(Period::LastNDays(days))
                 }
-                None => {}
             }
         }
     }
+
+    /// Working minutes per day and week, with averages and the tag and task breakdowns
+    fn draw_dashboard(&self, frame: &mut Frame, area: Rect) {
+        let dashboard = self.timer.get_dashboard();
+        let minutes = |secs: i64| (secs / 60) as u64;
+
+        let [charts_area, summary_area] =
+            Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)])
+                .areas(area);
+        let [days_area, weeks_area] =
+            Layout::vertical([Constraint::Min(6), Constraint::Length(5)]).areas(charts_area);
+
+        let bars: Vec<Bar> = dashboard
+            .days
+            .iter()
+            .map(|(day, secs)| {
+                Bar::default()
+                    .value(minutes(*secs))
+                    .label(Line::from(day.format("%d").to_string()))
+            })
+            .collect();
+        let days = BarChart::default()
+            .block(Block::bordered().title(format!("Work minutes, last {} days", DASHBOARD_DAYS)))
+            .bar_width(3)
+            .bar_gap(1)
+            .bar_style(Style::default().fg(Color::Red))
+            .value_style(Style::default().fg(Color::Black).bg(Color::Red))
+            .data(BarGroup::default().bars(&bars));
+        frame.render_widget(days, days_area);
+
+        let weekly: Vec<u64> = dashboard.weeks.iter().map(|secs| minutes(*secs)).collect();
+        let weeks = Sparkline::default()
+            .block(
+                Block::bordered().title(format!("Weekly totals, last {} weeks", DASHBOARD_WEEKS)),
+            )
+            .style(Style::default().fg(Color::Green))
+            .data(&weekly);
+        frame.render_widget(weeks, weeks_area);
+
+        let mut summary = vec![
+            Line::from(format!(
+                "Daily average: {}",
+                App::format_duration(dashboard.daily_average() as i32)
+            )),
+            Line::from(format!(
+                "Weekly average: {}",
+                App::format_duration(dashboard.weekly_average() as i32)
+            )),
+            Line::from(match dashboard.best_day() {
+                Some((day, secs)) => format!(
+                    "Best day: {} with {}",
+                    day.format("%a %Y-%m-%d"),
+                    App::format_duration(secs as i32)
+                ),
+                None => String::from("Best day: none yet"),
+            }),
+        ];
+        for (title, totals) in [("Tags", &dashboard.tags), ("Tasks", &dashboard.tasks)] {
+            summary.push(Line::from(""));
+            summary.push(Line::from(title.bold()));
+            if totals.is_empty() {
+                summary.push(Line::from("  none"));
+            }
+            for (name, secs) in totals {
+                summary.push(Line::from(format!(
+                    "  {}: {}",
+                    name,
+                    App::format_duration(*secs as i32)
+                )));
+            }
+        }
+        let summary = Paragraph::new(summary)
+            .block(Block::bordered().title(format!("Last {} days", DASHBOARD_DAYS)))
+            .wrap(Wrap { trim: false });
+        frame.render_widget(summary, summary_area);
+    }
+
+    /// Daily working time of a year, shaded like a GitHub contribution graph
+    fn draw_heatmap(&self, frame: &mut Frame, area: Rect, year: i32) {
+        let days = self.timer.get_daily_work_time(year);
+        let heatmap = Heatmap::new(year, self.timer.get_week_start(), &days)
+            .style(Style::default().fg(Color::Green));
+        let block = Block::bordered().title(format!(
+            "{}: {} of work, {}/{} for other years",
+            year,
+            App::format_duration(heatmap.total_secs() as i32),
+            self.keymap.keys(Action::PreviousYear),
+            self.keymap.keys(Action::NextYear)
+        ));
+        frame.render_widget(heatmap, block.inner(area));
+        frame.render_widget(block, area);
+    }
+
+    /// Show the view, or the messages again when it is already shown, whatever its year
+    fn toggle_view(&mut self, view: View) {
+        self.view = if std::mem::discriminant(&self.view) == std::mem::discriminant(&view) {
+            View::Messages
+        } else {
+            view
+        };
+    }
+
+    /// Share of the phase that has passed, 0 while idle
+    fn phase_elapsed_ratio(state: TimerState, remaining: Duration, total: Duration) -> f64 {
+        if state == TimerState::Idle || total.is_zero() {
+            return 0.0;
+        }
+        1.0 - remaining.min(total).as_secs_f64() / total.as_secs_f64()
+    }
+
+    /// Tomatoes for the pomodoros done in the long break cycle and today, like
+    /// `Cycle 🍅🍅·· Today 🍅🍅🍅🍅🍅`, with a count once there are too many to draw
+    fn tomato_line(cycle: Option<(u32, u32)>, today: i64) -> String {
+        let tomatoes = |count: usize| "🍅".repeat(count);
+
+        let mut line = String::new();
+        if let Some((done, length)) = cycle {
+            line.push_str(&format!(
+                "Cycle {}{}  ",
+                tomatoes(done as usize),
+                "·".repeat((length - done) as usize)
+            ));
+        }
+        line.push_str("Today ");
+        match today as usize {
+            0 => line.push_str("none yet"),
+            today if today > MAX_TOMATOES => line.push_str(&format!("{} ×{}", tomatoes(1), today)),
+            today => line.push_str(&tomatoes(today)),
+        }
+        line
+    }
+
+    /// Colour of the countdown, telling work and breaks apart from across the room
+    fn phase_color(state: TimerState) -> Color {
+        match state {
+            TimerState::Idle => Color::Gray,
+            TimerState::Working => Color::Red,
+            TimerState::Breaking => Color::Green,
+        }
+    }
+
+    /// `on` or `off`
+    fn parse_switch(arg: Option<&&str>) -> Option<bool> {
+        match arg {
+            Some(&"on") => Some(true),
+            Some(&"off") => Some(false),
+            _ => None,
+        }
+    }
+
+    /// The current durations and the signed in user's settings, one per line
+    fn format_settings(&self) -> String {
+        let settings = self.timer.get_user_settings();
+        let switch = |on: bool| if on { "on" } else { "off" };
+        let minutes = |secs: u64| format!("{}m", secs as f64 / 60.0);
+
+        let mut lines = vec![
+            format!(
+                "Settings of {}:",
+                self.timer
+                    .get_username()
+                    .unwrap_or(String::from("no one, login to save them"))
+            ),
+            format!(
+                "  working {}, breaking {}",
+                minutes(self.timer.get_work_duration().as_secs()),
+                minutes(self.timer.get_break_duration().as_secs())
+            ),
+        ];
+        lines.push(match settings.long_break_every {
+            0 => String::from("  long-break off"),
+            every => format!(
+                "  long-break {} every {} pomodoros",
+                minutes(settings.long_break_duration_secs),
+                every
+            ),
+        });
+        lines.push(format!(
+            "  auto-start {}, notifications {}, reflection {}",
+            switch(settings.auto_start),
+            switch(settings.notifications),
+            switch(settings.reflection_prompt)
+        ));
+        lines.push(format!(
+            "  week-start {}, day-start {}, timezone {}",
+            self.timer.get_week_start(),
+            settings.day_start_hour,
+            settings.time_zone.as_deref().unwrap_or("local")
+        ));
+        lines.join("\n")
+    }
+
+    fn settings_reply(result: Result<(), String>, reply: &mut Option<String>) -> MessageType {
+        match result {
+            Ok(()) => ValidCommand,
+            Err(e) => {
+                *reply = Some(e);
+                InvalidCommand
+            }
+        }
+    }
+
+    /// One line of the log, like `#12 2026-10-18 25m work 5m break #backend [report] "note"`
+    fn format_run(run: &RunRecord) -> String {
+        let mut line = format!(
+            "#{} {} {}m work {}m break",
+            run.id,
+            run.date,
+            run.working_time_secs / 60,
+            run.breaking_time_secs / 60
+        );
+        for tag in &run.tags {
+            line.push_str(&format!(" #{}", tag));
+        }
+        if let Some(task) = &run.task {
+            line.push_str(&format!(" [{}]", task));
+        }
+        if let Some(note) = &run.note {
+            line.push_str(&format!(" {:?}", note));
+        }
+        line
+    }
+
+    /// Format seconds as minutes, or as hours when over an hour
+    fn format_duration(duration_secs: i32) -> String {
+        let duration_min = duration_secs as f64 / 60.0;
+
+        if duration_min > 60.0 {
+            format!("{:.2} hours", duration_min / 60.0)
+        } else {
+            format!("{:.2} minutes", duration_min)
+        }
+    }
 }
@@ -0,0 +1,29 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use chrono::TimeDelta;
+
+/// Wrong passwords in a row before a user is locked out
+pub const MAX_FAILED_SIGN_INS: i32 = 5;
+
+/// How long a user is locked out after too many wrong passwords
+pub const LOCKOUT_DURATION: TimeDelta = TimeDelta::minutes(5);
+
+/// Hash a password with Argon2 and a random salt, as a PHC string like `$argon2id$v=19$...`
+pub fn hash_password(password: &str) -> String {
+    let salt = SaltString::generate(&mut OsRng);
+
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .expect("Error hashing password")
+        .to_string()
+}
+
+/// Whether the password matches a hash made by `hash_password`
+pub fn verify_password(password_hash: &str, password: &str) -> bool {
+    PasswordHash::new(password_hash).is_ok_and(|hash| {
+        Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok()
+    })
+}
@@ -1,4 +1,5 @@
 pub mod commands;
+pub mod credentials;
 pub mod models;
 pub mod pomodoro_timer;
 pub mod schema;
@@ -1,23 +1,180 @@
-use crate::core::schema::timer_runs;
-use chrono::NaiveDate;
+use crate::core::schema::{tags, tasks, timer_runs, users};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeDelta, Utc, Weekday};
+use chrono_tz::Tz;
 use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Queryable, Selectable)]
+/// A recorded run. Written back as a whole when a run is edited or a deletion is undone,
+/// so a `None` note or task clears it.
+#[derive(Queryable, QueryableByName, Selectable, Clone, Debug, PartialEq)]
 #[diesel(table_name = timer_runs)]
 #[diesel(check_for_backend(diesel::sqlite::Sqlite))]
 pub struct TimerRuns {
     pub id: i32,
-    pub user: String,
+    pub user_id: i32,
     pub working_time_secs: i32,
     pub breaking_time_secs: i32,
     pub date: NaiveDate,
+    pub note: Option<String>,
+    pub task_id: Option<i32>,
+    /// Stays the same when the run is merged into another database, unlike the id
+    pub uuid: String,
 }
 
-#[derive(Insertable)]
-#[diesel(table_name = timer_runs)]
 pub struct NewTimerRun<'a> {
-    pub user: &'a str,
+    pub user_id: &'a i32,
     pub working_time_secs: &'a i32,
     pub breaking_time_secs: &'a i32,
     pub date: &'a NaiveDate,
+    pub note: Option<&'a str>,
+    pub task_id: Option<&'a i32>,
+    /// A new `Uuid::new_v4()` for new runs
+    pub uuid: &'a str,
+}
+
+#[derive(Queryable, QueryableByName, Selectable)]
+#[diesel(table_name = tags)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Tag {
+    pub id: i32,
+    pub name: String,
+}
+
+#[derive(Queryable, QueryableByName, Selectable, Clone)]
+#[diesel(table_name = tasks)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Task {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub estimated_pomodoros: i32,
+    pub created: NaiveDate,
+}
+
+#[derive(Queryable, QueryableByName, Selectable, Clone)]
+#[diesel(table_name = users)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct User {
+    pub id: i32,
+    pub name: String,
+    pub created_at: NaiveDateTime,
+    pub settings: Option<String>,
+    /// Argon2 hash of the user's password, `None` when anyone may sign in as the user
+    pub password_hash: Option<String>,
+    /// Wrong passwords entered since the last successful sign in or lockout
+    pub failed_sign_ins: i32,
+    /// Signing in is refused until this time (UTC) after too many wrong passwords
+    pub locked_until: Option<NaiveDateTime>,
+}
+
+impl User {
+    /// The user's settings, the defaults if none are saved or they cannot be read
+    pub fn get_settings(&self) -> UserSettings {
+        self.settings
+            .as_deref()
+            .and_then(|settings| serde_json::from_str(settings).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// Settings of a user, saved as JSON in `users.settings`. Settings missing in the JSON,
+/// like those added after it was saved, get their default.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(default)]
+pub struct UserSettings {
+    /// Hour the day starts at. Runs ending before it count for the day before.
+    pub day_start_hour: u32,
+    /// IANA time zone like `Europe/Copenhagen`, the system's time zone when not set
+    pub time_zone: Option<String>,
+    /// Length of the work phase, the app's default when not set
+    pub work_duration_secs: Option<u64>,
+    /// Length of the break, the app's default when not set
+    pub break_duration_secs: Option<u64>,
+    /// Length of the long break
+    pub long_break_duration_secs: u64,
+    /// Every how many pomodoros the break is a long one, never when 0
+    pub long_break_every: u32,
+    /// Whether the next pomodoro starts by itself when a break is over
+    pub auto_start: bool,
+    /// Whether to show a desktop notification when a phase ends
+    pub notifications: bool,
+    /// Whether to ask for a note when a work phase ends
+    pub reflection_prompt: bool,
+    /// First day of the week for weekly stats
+    pub week_start: Weekday,
+}
+
+impl Default for UserSettings {
+    fn default() -> Self {
+        UserSettings {
+            day_start_hour: 0,
+            time_zone: None,
+            work_duration_secs: None,
+            break_duration_secs: None,
+            long_break_duration_secs: 15 * 60,
+            long_break_every: 0,
+            auto_start: false,
+            notifications: true,
+            reflection_prompt: true,
+            week_start: Weekday::Mon,
+        }
+    }
+}
+
+impl UserSettings {
+    /// The day `time` counts for in the user's time zone, with days starting at `day_start_hour`
+    pub fn date_at(&self, time: DateTime<Utc>) -> NaiveDate {
+        let local_time = match self
+            .time_zone
+            .as_deref()
+            .and_then(|tz| tz.parse::<Tz>().ok())
+        {
+            Some(tz) => time.with_timezone(&tz).naive_local(),
+            None => time.with_timezone(&Local).naive_local(),
+        };
+
+        (local_time - TimeDelta::hours(self.day_start_hour as i64)).date()
+    }
+
+    pub fn today(&self) -> NaiveDate {
+        self.date_at(Utc::now())
+    }
+}
+
+/// A timer run with its user, task and tags resolved, as used for exports and imports
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct RunRecord {
+    #[serde(default)]
+    pub id: i32,
+    pub date: NaiveDate,
+    #[serde(default)]
+    pub user: String,
+    pub working_time_secs: i32,
+    pub breaking_time_secs: i32,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub task: Option<String>,
+    pub note: Option<String>,
+    #[serde(default)]
+    pub uuid: String,
+}
+
+/// Summed up timer runs
+#[derive(QueryableByName, Default, Debug, PartialEq, Clone, Copy)]
+pub struct RunTotals {
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub working_time_secs: i64,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub breaking_time_secs: i64,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub runs: i64,
+}
+
+/// Summed up timer runs of a day, week, month or tag
+#[derive(QueryableByName, Debug, PartialEq, Clone)]
+pub struct GroupedRunTotals {
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub key: String,
+    #[diesel(embed)]
+    pub totals: RunTotals,
 }
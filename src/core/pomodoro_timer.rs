@@ -1,24 +1,57 @@
-use crate::core::pomodoro_timer::Period::Today;
+use crate::core::credentials::{
+    hash_password, verify_password, LOCKOUT_DURATION, MAX_FAILED_SIGN_INS,
+};
+use crate::core::models::{
+    GroupedRunTotals, NewTimerRun, RunRecord, Task, TimerRuns, User, UserSettings,
+};
 use crate::core::pomodoro_timer::TimerState::{Breaking, Idle, Working};
 use crate::core::timer_commander::TimerCommander;
 use crate::core::timer_runner::{ExitCondition, TimerRunner};
-use crate::db::timer_database::{
-    create_timer_run, establish_connection, get_timer_runs, get_users,
-};
-use chrono::Local;
+use crate::db::storage::Storage;
+use crate::db::timer_database::{write_run_records, ExportFormat, Grouping};
+use crate::db::timer_import::{import_runs, merge_database, ImportReport};
+use chrono::{Datelike, Days, Local, NaiveDate, NaiveDateTime, Utc, Weekday};
+use chrono_tz::Tz;
 use notify_rust::Notification;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::BufWriter;
+use std::path::Path;
 use std::sync::mpsc::Receiver;
-use std::sync::{mpsc, Arc, Mutex};
+use std::sync::{mpsc, Arc, Mutex, MutexGuard};
 use std::thread;
 use std::time::Duration;
+use uuid::Uuid;
 
 pub struct PomodoroTimer {
     work_duration: Duration,
     break_duration: Duration,
+    /// Durations for users who have not set their own
+    default_work_duration: Duration,
+    default_break_duration: Duration,
+    /// Pomodoros completed since signing in, to know when a long break is due
+    completed_pomodoros: Arc<Mutex<u32>>,
+    /// Full length of the phase the timer is in, which for breaks may be a long one
+    phase_duration: Arc<Mutex<Duration>>,
     current_state: Arc<Mutex<TimerState>>,
+    /// Whether the running phase is paused
+    paused: bool,
     commander: Option<TimerCommander>,
     receiver: Option<Receiver<Duration>>,
-    username: Option<String>,
+    user: Option<User>,
+    tags: Vec<String>,
+    note: Arc<Mutex<Option<String>>>,
+    task: Option<Task>,
+    /// First day of the week for weekly stats
+    week_start: Weekday,
+    /// Storage of runs, users and tasks, shared with the timer thread
+    storage: Arc<Mutex<dyn Storage>>,
+    /// The last change made to a recorded run, for `undo`
+    last_change: Option<RunChange>,
+    /// Whether signing in matches existing user names regardless of case
+    ignore_name_case: bool,
 }
 
 #[derive(PartialEq, Copy, Eq, Clone, Debug, Hash)]
@@ -28,29 +61,196 @@ pub enum TimerState {
     Breaking,
 }
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Copy, Clone, Debug)]
 pub enum Period {
     Today,
+    Yesterday,
+    /// From the start of the current week until today
+    ThisWeek,
+    LastWeek,
+    /// From the first of the current month until today
+    ThisMonth,
+    /// From the first of January until today
+    ThisYear,
+    /// The last n days, including today
+    LastNDays(u32),
+    /// From the first to the second date, both included
+    Range(NaiveDate, NaiveDate),
     AllTime,
 }
 
+impl Period {
+    /// First and last date of the period relative to `today`, or `None` when it is not limited.
+    /// Weeks start on `week_start`.
+    pub fn date_range(
+        &self,
+        today: NaiveDate,
+        week_start: Weekday,
+    ) -> Option<(NaiveDate, NaiveDate)> {
+        let days = |n: u32| Days::new(n as u64);
+        let start_of_week = today - days(today.weekday().days_since(week_start));
+
+        match *self {
+            Period::Today => Some((today, today)),
+            Period::Yesterday => Some((today - days(1), today - days(1))),
+            Period::ThisWeek => Some((start_of_week, today)),
+            Period::LastWeek => Some((start_of_week - days(7), start_of_week - days(1))),
+            Period::ThisMonth => Some((today.with_day(1)?, today)),
+            Period::ThisYear => Some((today.with_ordinal(1)?, today)),
+            // Starts at the first date there is when n reaches back further than that
+            Period::LastNDays(n) => Some((
+                today
+                    .checked_sub_days(days(n.max(1) - 1))
+                    .unwrap_or(NaiveDate::MIN),
+                today,
+            )),
+            Period::Range(from, to) => Some((from, to)),
+            Period::AllTime => None,
+        }
+    }
+}
+
+/// How well the tasks created in a month were estimated
+#[derive(PartialEq, Debug)]
+pub struct EstimateAccuracy {
+    /// Month the tasks were created in, as `YYYY-MM`
+    pub month: String,
+    /// Number of tasks with at least one completed pomodoro
+    pub tasks: usize,
+    /// Average of actual / estimated pomodoros
+    pub average_ratio: f64,
+    /// Number of tasks that were completed within their estimate
+    pub within_estimate: usize,
+}
+
+/// Days shown in the dashboard's bar chart and its tag and task breakdowns
+pub const DASHBOARD_DAYS: u32 = 14;
+/// Weeks shown in the dashboard's sparkline
+pub const DASHBOARD_WEEKS: u32 = 12;
+
+/// Working time the dashboard shows, see `PomodoroTimer::get_dashboard`
+#[derive(PartialEq, Debug, Default)]
+pub struct Dashboard {
+    /// Working seconds per day of the last `DASHBOARD_DAYS` days, oldest first
+    pub days: Vec<(NaiveDate, i64)>,
+    /// Working seconds per week of the last `DASHBOARD_WEEKS` weeks, oldest first,
+    /// the current week last
+    pub weeks: Vec<i64>,
+    /// Working seconds per tag in the last `DASHBOARD_DAYS` days, most first
+    pub tags: Vec<(String, i64)>,
+    /// Working seconds per task in the last `DASHBOARD_DAYS` days, most first
+    pub tasks: Vec<(String, i64)>,
+}
+
+impl Dashboard {
+    /// Average working seconds per day, days without runs included
+    pub fn daily_average(&self) -> i64 {
+        Dashboard::average(self.days.iter().map(|(_, secs)| *secs))
+    }
+
+    /// Average working seconds per week, the current week included
+    pub fn weekly_average(&self) -> i64 {
+        Dashboard::average(self.weeks.iter().copied())
+    }
+
+    /// The day with the most working time, the latest on a tie, `None` without any
+    pub fn best_day(&self) -> Option<(NaiveDate, i64)> {
+        self.days
+            .iter()
+            .copied()
+            .filter(|(_, secs)| *secs > 0)
+            .max_by_key(|(_, secs)| *secs)
+    }
+
+    fn average(values: impl ExactSizeIterator<Item = i64>) -> i64 {
+        let count = values.len() as i64;
+        if count == 0 {
+            return 0;
+        }
+        values.sum::<i64>() / count
+    }
+}
+
+/// A correction to a recorded run, see `PomodoroTimer::edit_run`
+#[derive(PartialEq, Clone, Debug)]
+pub enum RunEdit {
+    Date(NaiveDate),
+    WorkingTime(i32),
+    BreakingTime(i32),
+    Note(String),
+    /// Move the run to the user with this name
+    User(String),
+}
+
+/// Why `PomodoroTimer::sign_in` did not sign the user in
+#[derive(PartialEq, Clone, Debug)]
+pub enum SignInError {
+    /// Users cannot change while a pomodoro is running
+    TimerRunning,
+    /// The user has a password, but none was given
+    PasswordRequired,
+    WrongPassword,
+    /// Too many wrong passwords in a row, signing in is refused until this time (UTC)
+    LockedOut(NaiveDateTime),
+}
+
+impl fmt::Display for SignInError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignInError::TimerRunning => write!(f, "Stop the timer before changing users"),
+            SignInError::PasswordRequired => write!(f, "A password is needed"),
+            SignInError::WrongPassword => write!(f, "Wrong password"),
+            SignInError::LockedOut(until) => write!(
+                f,
+                "Too many wrong passwords, try again after {}",
+                until.and_utc().with_timezone(&Local).format("%H:%M")
+            ),
+        }
+    }
+}
+
+/// What `PomodoroTimer::undo` takes back
+enum RunChange {
+    Added(i32),
+    /// The run as it was before the edit
+    Edited(TimerRuns),
+    Deleted(TimerRuns, Vec<String>),
+}
+
 impl PomodoroTimer {
-    // Constructor that creates a new PomodoroTimer instance
-    pub fn new(work_duration_sec: u64, break_duration_sec: u64) -> PomodoroTimer {
+    // Constructor that creates a new PomodoroTimer instance storing its runs in `storage`
+    pub fn new(
+        work_duration_sec: u64,
+        break_duration_sec: u64,
+        storage: impl Storage + 'static,
+    ) -> PomodoroTimer {
         let pomodoro_timer = PomodoroTimer {
             work_duration: Duration::from_secs(work_duration_sec),
             break_duration: Duration::from_secs(break_duration_sec),
+            default_work_duration: Duration::from_secs(work_duration_sec),
+            default_break_duration: Duration::from_secs(break_duration_sec),
+            completed_pomodoros: Arc::new(Mutex::new(0)),
+            phase_duration: Arc::new(Mutex::new(Duration::ZERO)),
             current_state: Arc::new(Mutex::new(Idle)),
+            paused: false,
             commander: None,
             receiver: None,
-            username: None,
+            user: None,
+            tags: Vec::new(),
+            note: Arc::new(Mutex::new(None)),
+            task: None,
+            week_start: Weekday::Mon,
+            storage: Arc::new(Mutex::new(storage)),
+            last_change: None,
+            ignore_name_case: false,
         };
 
         // Create the new timer instance
         pomodoro_timer
     }
 
-    // A run being an "Idle -> Working -> Break -> Idle" iteration
+    // A run being an "Idle -> Working -> Break -> Idle" iteration. With auto start the
+    // next run follows right after the break, until the timer is stopped.
     pub fn start_run(&mut self) {
         // Create new Timer runner
         let (tx, rx) = mpsc::channel();
@@ -63,57 +263,94 @@ impl PomodoroTimer {
 
         self.commander = Some(timer_commander);
         self.receiver = Some(time_rx);
+        self.paused = false;
 
         // Save the times in separate variables
         let working_duration = self.work_duration;
-        let break_duration = self.break_duration;
-        let username = self.username.clone();
+        let short_break_duration = self.break_duration;
+        let user_id = self.user.as_ref().map(|user| user.id);
+        let settings = self.get_user_settings();
+        let tags = self.tags.clone();
         let current_state = Arc::clone(&self.current_state);
+        let note = Arc::clone(&self.note);
+        let task_id = self.task.as_ref().map(|task| task.id);
+        let storage = Arc::clone(&self.storage);
+        let completed_pomodoros = Arc::clone(&self.completed_pomodoros);
+        let phase_duration = Arc::clone(&self.phase_duration);
+
+        // A new run starts without a note
+        *note.lock().expect("Failed to lock note") = None;
 
-        thread::spawn(move || {
+        thread::spawn(move || loop {
             // Run through the phases
             // Start in working phase
-            PomodoroTimer::update_state(&current_state, Working);
-            let exit_condition = timer_runner.run_timer(working_duration);
-
-            if exit_condition == ExitCondition::Terminated {
+            PomodoroTimer::update_phase(&current_state, &phase_duration, Working, working_duration);
+            let ExitCondition::Ok(worked) = timer_runner.run_timer(working_duration) else {
                 PomodoroTimer::update_state(&current_state, Idle);
                 return;
-            }
+            };
 
-            Notification::new()
-                .summary("PomodoroTimer")
-                .body("Good work! Take a break before continuing.")
-                .show()
-                .ok();
-
-            // Then breaking phase
-            PomodoroTimer::update_state(&current_state, Breaking);
-            let exit_condition = timer_runner.run_timer(break_duration);
+            if settings.notifications {
+                Notification::new()
+                    .summary("PomodoroTimer")
+                    .body("Good work! Take a break before continuing.")
+                    .show()
+                    .ok();
+            }
 
-            if exit_condition == ExitCondition::Terminated {
+            // Then breaking phase, a long one every `long_break_every` pomodoros
+            let completed = *completed_pomodoros
+                .lock()
+                .expect("Failed to lock completed pomodoros")
+                + 1;
+            let break_duration = if settings.long_break_every > 0
+                && completed.is_multiple_of(settings.long_break_every)
+            {
+                Duration::from_secs(settings.long_break_duration_secs)
+            } else {
+                short_break_duration
+            };
+            PomodoroTimer::update_phase(&current_state, &phase_duration, Breaking, break_duration);
+            let ExitCondition::Ok(rested) = timer_runner.run_timer(break_duration) else {
                 PomodoroTimer::update_state(&current_state, Idle);
                 return;
-            }
+            };
 
-            Notification::new()
-                .summary("PomodoroTimer")
-                .body("The break is over! Continue with your good work.")
-                .show()
-                .ok();
+            if settings.notifications {
+                Notification::new()
+                    .summary("PomodoroTimer")
+                    .body("The break is over! Continue with your good work.")
+                    .show()
+                    .ok();
+            }
 
-            // Then return to idle
-            PomodoroTimer::update_state(&current_state, Idle);
+            // Then return to idle, unless the next run starts right away
+            if !settings.auto_start {
+                PomodoroTimer::update_state(&current_state, Idle);
+            }
+            *completed_pomodoros
+                .lock()
+                .expect("Failed to lock completed pomodoros") = completed;
 
             // Log the completed iteration in the database
-            if username.is_some() {
-                let connection = &mut establish_connection();
-                create_timer_run(
-                    connection,
-                    &*username.unwrap(),
-                    &(working_duration.as_secs() as i32),
-                    &(break_duration.as_secs() as i32),
-                )
+            if let Some(user_id) = user_id {
+                let note = note.lock().expect("Failed to lock note").take();
+                PomodoroTimer::lock_storage(&storage).insert_timer_run(
+                    &NewTimerRun {
+                        user_id: &user_id,
+                        working_time_secs: &(worked.as_secs_f64().round() as i32),
+                        breaking_time_secs: &(rested.as_secs_f64().round() as i32),
+                        date: &settings.date_at(Utc::now()),
+                        note: note.as_deref(),
+                        task_id: task_id.as_ref(),
+                        uuid: &Uuid::new_v4().to_string(),
+                    },
+                    &tags,
+                );
+            }
+
+            if !settings.auto_start {
+                return;
             }
         });
     }
@@ -123,6 +360,22 @@ impl PomodoroTimer {
         *current_state = new_state;
     }
 
+    fn update_phase(
+        state: &Arc<Mutex<TimerState>>,
+        phase_duration: &Arc<Mutex<Duration>>,
+        new_state: TimerState,
+        duration: Duration,
+    ) {
+        *phase_duration
+            .lock()
+            .expect("Failed to lock phase duration") = duration;
+        PomodoroTimer::update_state(state, new_state);
+    }
+
+    fn lock_storage(storage: &Arc<Mutex<dyn Storage>>) -> MutexGuard<'_, dyn Storage + 'static> {
+        storage.lock().expect("Failed to lock storage")
+    }
+
     pub fn get_state(&self) -> TimerState {
         let current_state = self
             .current_state
@@ -152,8 +405,66 @@ impl PomodoroTimer {
 
         match &mut self.commander {
             None => println!("Have to start a sessions to give commands"),
-            Some(c) => c.pause_timer(),
+            Some(c) => {
+                self.paused = true;
+                c.pause_timer();
+            }
+        }
+    }
+
+    /// Whether a phase is running but paused
+    pub fn is_paused(&self) -> bool {
+        self.paused && self.get_state() != Idle
+    }
+
+    /// End the current phase early: a pomodoro goes on to its break, a break ends the run.
+    /// Skipping a paused phase resumes the timer.
+    pub fn skip_phase(&mut self) -> Result<(), String> {
+        let Some(c) = self.running_commander() else {
+            return Err(String::from("There is no phase to skip"));
+        };
+        c.skip_phase();
+        self.paused = false;
+        Ok(())
+    }
+
+    /// Make the current phase longer, refused when it would get longer than a `Duration` can be
+    pub fn extend_phase(&mut self, by: Duration) -> Result<(), String> {
+        let phase_duration = Arc::clone(&self.phase_duration);
+        let mut phase_duration = phase_duration
+            .lock()
+            .expect("Failed to lock phase duration");
+        let Some(c) = self.running_commander() else {
+            return Err(String::from("There is no phase to extend"));
+        };
+        let Some(extended) = phase_duration.checked_add(by) else {
+            return Err(String::from("The phase cannot get that long"));
+        };
+        c.extend_phase(by);
+        *phase_duration = extended;
+        Ok(())
+    }
+
+    /// Make the current phase shorter, ending it when less than `by` is left
+    pub fn shorten_phase(&mut self, by: Duration) -> Result<(), String> {
+        let Some(c) = self.running_commander() else {
+            return Err(String::from("There is no phase to shorten"));
+        };
+        c.shorten_phase(by);
+        let mut phase_duration = self
+            .phase_duration
+            .lock()
+            .expect("Failed to lock phase duration");
+        *phase_duration = phase_duration.saturating_sub(by);
+        Ok(())
+    }
+
+    /// The commander of the running session, if there is one
+    fn running_commander(&mut self) -> Option<&mut TimerCommander> {
+        if self.get_state() == Idle {
+            return None;
         }
+        self.commander.as_mut()
     }
 
     pub fn stop_timer(&mut self) {
@@ -165,6 +476,7 @@ impl PomodoroTimer {
             None => println!("Have to start a sessions to give commands"),
             Some(c) => {
                 PomodoroTimer::update_state(&self.current_state, Idle);
+                self.paused = false;
                 c.stop_timer();
             }
         }
@@ -173,7 +485,10 @@ impl PomodoroTimer {
     pub fn resume_timer(&mut self) {
         match &mut self.commander {
             None => println!("Have to start a sessions to give commands"),
-            Some(c) => c.resume_timer(),
+            Some(c) => {
+                self.paused = false;
+                c.resume_timer();
+            }
         }
     }
 
@@ -213,70 +528,781 @@ impl PomodoroTimer {
         }
     }
 
+    /// Full length of the current phase, the work duration while idle
+    pub fn get_phase_duration(&self) -> Duration {
+        match self.get_state() {
+            Idle => self.work_duration,
+            Working | Breaking => *self
+                .phase_duration
+                .lock()
+                .expect("Failed to lock phase duration"),
+        }
+    }
+
+    /// Pomodoros completed in the current long break cycle and the length of the cycle,
+    /// `None` when the signed in user takes no long breaks
+    pub fn get_cycle_progress(&self) -> Option<(u32, u32)> {
+        let every = self.get_user_settings().long_break_every;
+        if every == 0 {
+            return None;
+        }
+
+        let completed = *self
+            .completed_pomodoros
+            .lock()
+            .expect("Failed to lock completed pomodoros");
+        Some((completed % every, every))
+    }
+
+    /// Number of pomodoros the signed in user completed today
+    pub fn get_pomodoros_today(&self) -> i64 {
+        let Some(user) = self.get_user_id() else {
+            return 0;
+        };
+
+        let mut storage = PomodoroTimer::lock_storage(&self.storage);
+        storage
+            .sum_timer_runs(&user, self.date_range(Period::Today), None)
+            .runs
+    }
+
+    /// Set the work or break duration, saved in the settings of the signed in user
     pub fn set_state_time_period(&mut self, period: Duration, state: TimerState) {
         // Stop timer when user updates duration
         self.stop_timer();
 
+        let mut settings = self.get_user_settings();
         match state {
             Idle => {}
             Working => {
                 self.work_duration = period;
+                settings.work_duration_secs = Some(period.as_secs());
             }
-            Breaking => self.break_duration = period,
+            Breaking => {
+                self.break_duration = period;
+                settings.break_duration_secs = Some(period.as_secs());
+            }
+        }
+
+        if self.is_user_signed_in() {
+            self.save_user_settings(settings).ok();
         }
     }
 
     pub fn get_total_time(&self, period: Period) -> (i32, i32) {
-        let connection = &mut establish_connection();
+        // Check that user is logged in
+        let Some(user) = self.get_user_id() else {
+            return (0, 0);
+        };
 
-        let user = self.get_username();
+        let mut storage = PomodoroTimer::lock_storage(&self.storage);
+        let totals = storage.sum_timer_runs(&user, self.date_range(period), None);
 
-        // Check that user is logged in
-        if user.is_none() {
+        (
+            totals.working_time_secs as i32,
+            totals.breaking_time_secs as i32,
+        )
+    }
+
+    /// Total working and breaking time of the runs tagged with `tag` in the given period
+    pub fn get_total_time_for_tag(&self, period: Period, tag: &str) -> (i32, i32) {
+        let Some(user) = self.get_user_id() else {
             return (0, 0);
-        }
+        };
+
+        let mut storage = PomodoroTimer::lock_storage(&self.storage);
+        let totals = storage.sum_timer_runs(&user, self.date_range(period), Some(tag));
+
+        (
+            totals.working_time_secs as i32,
+            totals.breaking_time_secs as i32,
+        )
+    }
 
-        let mut runs = get_timer_runs(connection, &*user.unwrap());
+    /// Total working and breaking time per tag in the given period, sorted by tag name
+    pub fn get_tag_breakdown(&self, period: Period) -> Vec<(String, i32, i32)> {
+        let Some(user) = self.get_user_id() else {
+            return Vec::new();
+        };
 
-        let cur_date = Local::now().date_naive();
+        let mut storage = PomodoroTimer::lock_storage(&self.storage);
+        storage
+            .get_tag_run_totals(&user, self.date_range(period))
+            .into_iter()
+            .map(|tag| {
+                (
+                    tag.key,
+                    tag.totals.working_time_secs as i32,
+                    tag.totals.breaking_time_secs as i32,
+                )
+            })
+            .collect()
+    }
 
-        // Filter out all dates in case only today
-        if period == Today {
-            runs = runs
+    /// Working time per day and week, tag and task for the dashboard, empty when no one is
+    /// signed in
+    pub fn get_dashboard(&self) -> Dashboard {
+        let Some(user) = self.get_user_id() else {
+            return Dashboard::default();
+        };
+
+        let today = self.get_user_settings().today();
+        let Some((start_of_week, _)) = Period::ThisWeek.date_range(today, self.week_start) else {
+            return Dashboard::default();
+        };
+        let first_week = start_of_week - Days::new(7 * (DASHBOARD_WEEKS as u64 - 1));
+        let first_day = today - Days::new(DASHBOARD_DAYS as u64 - 1);
+        let recent = self.date_range(Period::LastNDays(DASHBOARD_DAYS));
+
+        let mut storage = PomodoroTimer::lock_storage(&self.storage);
+        let per_day = PomodoroTimer::work_time_per_day(&mut *storage, &user, (first_day, today));
+        let weekly = Grouping::Week(self.week_start);
+        let per_week: HashMap<String, i64> = storage
+            .get_grouped_run_totals(&user, Some((first_week, today)), weekly)
+            .into_iter()
+            .map(|week| (week.key, week.totals.working_time_secs))
+            .collect();
+        let by_working_time = |groups: Vec<GroupedRunTotals>| {
+            let mut totals: Vec<(String, i64)> = groups
                 .into_iter()
-                .filter(|tr| tr.date.eq(&cur_date))
+                .map(|group| (group.key, group.totals.working_time_secs))
                 .collect();
+            totals.sort_by_key(|(_, secs)| std::cmp::Reverse(*secs));
+            totals
+        };
+
+        Dashboard {
+            days: first_day
+                .iter_days()
+                .take(DASHBOARD_DAYS as usize)
+                .map(|day| (day, per_day.get(&day).copied().unwrap_or(0)))
+                .collect(),
+            weeks: first_week
+                .iter_weeks()
+                .take(DASHBOARD_WEEKS as usize)
+                .map(|week| per_week.get(&weekly.key(week)).copied().unwrap_or(0))
+                .collect(),
+            tags: by_working_time(storage.get_tag_run_totals(&user, recent)),
+            tasks: by_working_time(storage.get_task_run_totals(&user, recent)),
+        }
+    }
+
+    /// Working seconds per day of the given year, days without runs are left out
+    pub fn get_daily_work_time(&self, year: i32) -> BTreeMap<NaiveDate, i64> {
+        let Some(user) = self.get_user_id() else {
+            return BTreeMap::new();
+        };
+        let (Some(first), Some(last)) = (
+            NaiveDate::from_ymd_opt(year, 1, 1),
+            NaiveDate::from_ymd_opt(year, 12, 31),
+        ) else {
+            return BTreeMap::new();
+        };
+
+        let mut storage = PomodoroTimer::lock_storage(&self.storage);
+        PomodoroTimer::work_time_per_day(&mut *storage, &user, (first, last))
+    }
+
+    fn work_time_per_day(
+        storage: &mut dyn Storage,
+        user: &i32,
+        date_range: (NaiveDate, NaiveDate),
+    ) -> BTreeMap<NaiveDate, i64> {
+        storage
+            .get_grouped_run_totals(user, Some(date_range), Grouping::Day)
+            .into_iter()
+            .filter_map(|day| {
+                let date = NaiveDate::parse_from_str(&day.key, "%Y-%m-%d").ok()?;
+                Some((date, day.totals.working_time_secs))
+            })
+            .collect()
+    }
+
+    fn date_range(&self, period: Period) -> Option<(NaiveDate, NaiveDate)> {
+        period.date_range(self.get_user_settings().today(), self.week_start)
+    }
+
+    /// The signed in user's settings, the defaults when no one is signed in
+    pub fn get_user_settings(&self) -> UserSettings {
+        self.user
+            .as_ref()
+            .map(User::get_settings)
+            .unwrap_or_default()
+    }
+
+    /// Let the signed in user's days start at the hour, so runs ending before it count
+    /// for the day before
+    pub fn set_day_start_hour(&mut self, hour: u32) -> Result<(), String> {
+        if hour >= 24 {
+            return Err(String::from("The day can start at hour 0 to 23"));
+        }
+
+        let mut settings = self.get_user_settings();
+        settings.day_start_hour = hour;
+        self.save_user_settings(settings)
+    }
+
+    /// Set the signed in user's time zone, like `Europe/Copenhagen`. `None` uses the system's.
+    pub fn set_time_zone(&mut self, time_zone: Option<&str>) -> Result<(), String> {
+        if let Some(time_zone) = time_zone {
+            time_zone
+                .parse::<Tz>()
+                .map_err(|_| format!("Unknown time zone {}", time_zone))?;
+        }
+
+        let mut settings = self.get_user_settings();
+        settings.time_zone = time_zone.map(String::from);
+        self.save_user_settings(settings)
+    }
+
+    /// Take a long break of `duration` every `every` pomodoros, or never with 0
+    pub fn set_long_break(&mut self, duration: Duration, every: u32) -> Result<(), String> {
+        let mut settings = self.get_user_settings();
+        settings.long_break_duration_secs = duration.as_secs();
+        settings.long_break_every = every;
+        self.save_user_settings(settings)
+    }
+
+    /// Whether the next pomodoro starts by itself when a break is over
+    pub fn set_auto_start(&mut self, auto_start: bool) -> Result<(), String> {
+        let mut settings = self.get_user_settings();
+        settings.auto_start = auto_start;
+        self.save_user_settings(settings)
+    }
+
+    /// Whether to show desktop notifications when a phase ends
+    pub fn set_notifications(&mut self, notifications: bool) -> Result<(), String> {
+        let mut settings = self.get_user_settings();
+        settings.notifications = notifications;
+        self.save_user_settings(settings)
+    }
+
+    /// Whether to ask for a note when a work phase ends
+    pub fn set_reflection_prompt(&mut self, reflection_prompt: bool) -> Result<(), String> {
+        let mut settings = self.get_user_settings();
+        settings.reflection_prompt = reflection_prompt;
+        self.save_user_settings(settings)
+    }
+
+    /// Use the signed in user's durations and week start, or the defaults for what they did
+    /// not set. Starts a new long break cycle.
+    fn apply_user_settings(&mut self) {
+        let settings = self.get_user_settings();
+
+        self.work_duration = settings
+            .work_duration_secs
+            .map_or(self.default_work_duration, Duration::from_secs);
+        self.break_duration = settings
+            .break_duration_secs
+            .map_or(self.default_break_duration, Duration::from_secs);
+        self.week_start = settings.week_start;
+        *self
+            .completed_pomodoros
+            .lock()
+            .expect("Failed to lock completed pomodoros") = 0;
+    }
+
+    fn save_user_settings(&mut self, settings: UserSettings) -> Result<(), String> {
+        let Some(user) = self.user.as_mut() else {
+            return Err(String::from("You have to login to change your settings"));
         };
 
-        // Work out the total amount of time used today
-        runs.into_iter().fold((0, 0), |acc, tr| {
-            let (working, breaking) = acc;
-            (
-                working + tr.working_time_secs,
-                breaking + tr.breaking_time_secs,
-            )
-        })
+        let mut storage = PomodoroTimer::lock_storage(&self.storage);
+        storage.save_user_settings(&user.id, &settings);
+        user.settings = Some(serde_json::to_string(&settings).expect("Error writing settings"));
+        Ok(())
     }
 
-    // Dummy sign in
-    pub fn sign_in(&mut self, username: &str) -> bool {
+    pub fn get_week_start(&self) -> Weekday {
+        self.week_start
+    }
+
+    /// Set the first day of the week, saved in the settings of the signed in user
+    pub fn set_week_start(&mut self, week_start: Weekday) {
+        self.week_start = week_start;
+
+        let mut settings = self.get_user_settings();
+        settings.week_start = week_start;
+        if self.is_user_signed_in() {
+            self.save_user_settings(settings).ok();
+        }
+    }
+
+    /// Set the tags the next started run is recorded with
+    pub fn set_tags(&mut self, tags: Vec<String>) {
+        self.tags = tags;
+    }
+
+    pub fn get_tags(&self) -> Vec<String> {
+        self.tags.clone()
+    }
+
+    /// Attach a note to the ongoing run, or to the last recorded run when idle.
+    /// Returns false if there is no run to attach it to.
+    pub fn set_note(&mut self, text: &str) -> bool {
+        let Some(user) = self.get_user_id() else {
+            return false;
+        };
+
         if self.get_state() != Idle {
+            // The run is recorded when the break ends, so keep the note until then
+            *self.note.lock().expect("Failed to lock note") = Some(text.to_string());
+            return true;
+        }
+
+        let mut storage = PomodoroTimer::lock_storage(&self.storage);
+        match storage.get_last_timer_run_id(&user) {
+            Some(timer_run_id) => {
+                storage.set_timer_run_note(&timer_run_id, text);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Work on the named task in the next runs, creating it if it does not exist.
+    /// A given estimate replaces the task's current one; new tasks default to one pomodoro.
+    /// Returns false if no user is signed in.
+    pub fn set_task(&mut self, task_name: &str, estimated_pomodoros: Option<i32>) -> bool {
+        let Some(user) = self.get_user_id() else {
             return false;
+        };
+
+        let today = self.get_user_settings().today();
+        let mut storage = PomodoroTimer::lock_storage(&self.storage);
+        let task = match (storage.get_task(&user, task_name), estimated_pomodoros) {
+            (Some(task), None) => task,
+            (_, estimate) => storage.save_task(&user, task_name, &estimate.unwrap_or(1), &today),
+        };
+
+        self.task = Some(task);
+        true
+    }
+
+    pub fn clear_task(&mut self) {
+        self.task = None;
+    }
+
+    pub fn get_task_name(&self) -> Option<String> {
+        self.task.as_ref().map(|task| task.name.clone())
+    }
+
+    /// Estimated and completed pomodoros of the current task
+    pub fn get_task_progress(&self) -> Option<(i32, i64)> {
+        let task_id = self.task.as_ref()?.id;
+
+        self.get_tasks()
+            .into_iter()
+            .find(|(task, _)| task.id == task_id)
+            .map(|(task, actual)| (task.estimated_pomodoros, actual))
+    }
+
+    /// The user's tasks with the number of completed pomodoros for each
+    pub fn get_tasks(&self) -> Vec<(Task, i64)> {
+        let Some(user) = self.get_user_id() else {
+            return Vec::new();
+        };
+
+        let mut storage = PomodoroTimer::lock_storage(&self.storage);
+        storage.get_tasks_with_actuals(&user)
+    }
+
+    /// Estimate accuracy of the user's tasks, grouped by the month they were created in
+    pub fn get_estimate_accuracy(&self) -> Vec<EstimateAccuracy> {
+        let mut months: BTreeMap<String, Vec<(i32, i64)>> = BTreeMap::new();
+        for (task, actual) in self.get_tasks() {
+            // Tasks not worked on yet say nothing about the estimate
+            if actual == 0 {
+                continue;
+            }
+            months
+                .entry(task.created.format("%Y-%m").to_string())
+                .or_default()
+                .push((task.estimated_pomodoros, actual));
         }
-        self.username = Some(username.to_string());
+
+        months
+            .into_iter()
+            .map(|(month, tasks)| EstimateAccuracy {
+                average_ratio: tasks
+                    .iter()
+                    .map(|(estimate, actual)| *actual as f64 / (*estimate).max(1) as f64)
+                    .sum::<f64>()
+                    / tasks.len() as f64,
+                within_estimate: tasks
+                    .iter()
+                    .filter(|(estimate, actual)| *actual <= *estimate as i64)
+                    .count(),
+                tasks: tasks.len(),
+                month,
+            })
+            .collect()
+    }
+
+    /// Export the signed in user's runs, optionally only those within the inclusive date range.
+    /// Returns the number of exported runs.
+    pub fn export_runs(
+        &self,
+        path: &Path,
+        format: ExportFormat,
+        date_range: Option<(NaiveDate, NaiveDate)>,
+    ) -> io::Result<usize> {
+        let Some(user) = self.get_user_id() else {
+            return Err(io::Error::other("No user is signed in"));
+        };
+
+        let mut storage = PomodoroTimer::lock_storage(&self.storage);
+        let records = storage.get_run_records(&user, date_range);
+        write_run_records(&records, format, BufWriter::new(File::create(path)?))?;
+
+        Ok(records.len())
+    }
+
+    /// Import runs from a file for the signed in user. With `dry_run` nothing is saved.
+    pub fn import_runs(
+        &self,
+        path: &Path,
+        format: ExportFormat,
+        dry_run: bool,
+    ) -> io::Result<ImportReport> {
+        let Some(user) = self.get_user_id() else {
+            return Err(io::Error::other("No user is signed in"));
+        };
+
+        let mut storage = PomodoroTimer::lock_storage(&self.storage);
+        import_runs(&mut *storage, &user, path, format, dry_run)
+    }
+
+    /// The signed in user's last `count` runs, the most recent first
+    pub fn get_recent_runs(&self, count: usize) -> Vec<RunRecord> {
+        let Some(user) = self.get_user_id() else {
+            return vec![];
+        };
+
+        let mut storage = PomodoroTimer::lock_storage(&self.storage);
+        let mut records = storage.get_run_records(&user, None);
+        records.reverse();
+        records.truncate(count);
+        records
+    }
+
+    /// Record a run by hand for the signed in user, returning its id
+    pub fn add_run(
+        &mut self,
+        date: NaiveDate,
+        working_time_secs: i32,
+        breaking_time_secs: i32,
+    ) -> Option<i32> {
+        let user = self.get_user_id()?;
+
+        let mut storage = PomodoroTimer::lock_storage(&self.storage);
+        let timer_run_id = storage.insert_timer_run(
+            &NewTimerRun {
+                user_id: &user,
+                working_time_secs: &working_time_secs,
+                breaking_time_secs: &breaking_time_secs,
+                date: &date,
+                note: None,
+                task_id: None,
+                uuid: &Uuid::new_v4().to_string(),
+            },
+            &[],
+        );
+
+        self.last_change = Some(RunChange::Added(timer_run_id));
+        Some(timer_run_id)
+    }
+
+    /// Correct one of the signed in user's runs. A run moved to another user goes to their
+    /// task of the same name, or to no task if they have none.
+    pub fn edit_run(&mut self, timer_run_id: i32, edit: RunEdit) -> Result<(), String> {
+        let Some(before) = self.get_own_run(timer_run_id).map(|(run, _)| run) else {
+            return Err(format!("You have no run #{}", timer_run_id));
+        };
+
+        let mut storage = PomodoroTimer::lock_storage(&self.storage);
+        let mut after = before.clone();
+        match edit {
+            RunEdit::Date(date) => after.date = date,
+            RunEdit::WorkingTime(secs) => after.working_time_secs = secs,
+            RunEdit::BreakingTime(secs) => after.breaking_time_secs = secs,
+            RunEdit::Note(note) => after.note = Some(note),
+            RunEdit::User(username) => {
+                let Some(user) = storage.get_user(&username) else {
+                    return Err(format!("There is no user {}", username));
+                };
+                self.check_may_change_user(&mut *storage, &username)?;
+
+                let task_name = before.task_id.and_then(|task_id| {
+                    storage
+                        .get_tasks_with_actuals(&before.user_id)
+                        .into_iter()
+                        .find(|(task, _)| task.id == task_id)
+                        .map(|(task, _)| task.name)
+                });
+                after.user_id = user.id;
+                after.task_id = task_name
+                    .and_then(|name| storage.get_task(&user.id, &name))
+                    .map(|task| task.id);
+            }
+        }
+        storage.update_timer_run(&after);
+
+        self.last_change = Some(RunChange::Edited(before));
+        Ok(())
+    }
+
+    /// Delete one of the signed in user's runs. Returns false if there is no such run.
+    pub fn delete_run(&mut self, timer_run_id: i32) -> bool {
+        let Some((run, tags)) = self.get_own_run(timer_run_id) else {
+            return false;
+        };
+
+        let mut storage = PomodoroTimer::lock_storage(&self.storage);
+        storage.delete_timer_run(&timer_run_id);
+
+        self.last_change = Some(RunChange::Deleted(run, tags));
         true
     }
 
+    /// Take back the last run added, edited or deleted, returning the id of that run
+    pub fn undo(&mut self) -> Option<i32> {
+        let change = self.last_change.take()?;
+
+        let mut storage = PomodoroTimer::lock_storage(&self.storage);
+        match change {
+            RunChange::Added(timer_run_id) => {
+                storage.delete_timer_run(&timer_run_id);
+                Some(timer_run_id)
+            }
+            RunChange::Edited(before) => {
+                storage.update_timer_run(&before);
+                Some(before.id)
+            }
+            RunChange::Deleted(run, tags) => {
+                storage.reinsert_timer_run(&run, &tags);
+                Some(run.id)
+            }
+        }
+    }
+
+    /// A run with its tags, if it is the signed in user's
+    fn get_own_run(&self, timer_run_id: i32) -> Option<(TimerRuns, Vec<String>)> {
+        let user = self.get_user_id()?;
+
+        let mut storage = PomodoroTimer::lock_storage(&self.storage);
+        storage
+            .get_timer_run(&timer_run_id)
+            .filter(|(run, _)| run.user_id == user)
+    }
+
+    /// Write a snapshot of the whole database to a new file
+    pub fn backup(&self, path: &Path) -> io::Result<()> {
+        let mut storage = PomodoroTimer::lock_storage(&self.storage);
+        storage.backup(path)
+    }
+
+    /// Replace the whole database with a backup. Only while the timer is idle, as the
+    /// running pomodoro would be recorded in the replaced database.
+    pub fn restore(&mut self, path: &Path) -> io::Result<()> {
+        if self.get_state() != Idle {
+            return Err(io::Error::other("Stop the timer before restoring"));
+        }
+
+        let mut storage = PomodoroTimer::lock_storage(&self.storage);
+        storage.restore(path)?;
+
+        // Ids may differ in the backup, so look the user up again
+        if let Some(user) = &self.user {
+            self.user = Some(storage.get_or_create_user(&user.name));
+        }
+        self.task = None;
+        self.last_change = None;
+        drop(storage);
+        self.apply_user_settings();
+        Ok(())
+    }
+
+    /// Add the runs of all users from another database file that are not in this one yet
+    pub fn merge_database(&self, path: &Path) -> io::Result<ImportReport> {
+        let mut storage = PomodoroTimer::lock_storage(&self.storage);
+        merge_database(&mut *storage, path)
+    }
+
+    /// Sign in as the user with the given name, creating the user if needed. When ignoring
+    /// case, an existing user whose name only differs in case is signed in instead.
+    /// Users with a password need it, see `SignInError`.
+    pub fn sign_in(&mut self, username: &str, password: Option<&str>) -> Result<(), SignInError> {
+        if self.get_state() != Idle {
+            return Err(SignInError::TimerRunning);
+        }
+        let mut storage = PomodoroTimer::lock_storage(&self.storage);
+
+        let mut username = username.to_string();
+        if self.ignore_name_case {
+            let users = storage.get_users();
+            if !users.contains(&username) {
+                if let Some(existing) = users
+                    .into_iter()
+                    .find(|name| name.to_lowercase() == username.to_lowercase())
+                {
+                    username = existing;
+                }
+            }
+        }
+
+        let user = storage.get_or_create_user(&username);
+        PomodoroTimer::check_password(&mut *storage, &user, password)?;
+
+        self.user = Some(user);
+        self.task = None;
+        drop(storage);
+        self.apply_user_settings();
+        Ok(())
+    }
+
+    /// Whether the signed in user has a password
+    pub fn has_password(&self) -> bool {
+        self.user
+            .as_ref()
+            .is_some_and(|user| user.password_hash.is_some())
+    }
+
+    /// Set the signed in user's password, or remove it with `None`. Changing an existing
+    /// password needs the current one, and counts towards a lockout like signing in.
+    pub fn set_password(
+        &mut self,
+        current_password: Option<&str>,
+        new_password: Option<&str>,
+    ) -> Result<(), String> {
+        let Some(username) = self.get_username() else {
+            return Err(String::from("Sign in first"));
+        };
+        let mut storage = PomodoroTimer::lock_storage(&self.storage);
+
+        let user = storage.get_or_create_user(&username);
+        PomodoroTimer::check_password(&mut *storage, &user, current_password)
+            .map_err(|e| e.to_string())?;
+
+        storage.save_password_hash(&user.id, new_password.map(hash_password).as_deref());
+        self.user = Some(storage.get_or_create_user(&username));
+        Ok(())
+    }
+
+    /// Let a user without a password through, and a user with one only with the right password
+    /// and while not locked out. Wrong passwords are counted, and lock the user out once there
+    /// are `MAX_FAILED_SIGN_INS` in a row.
+    fn check_password(
+        storage: &mut dyn Storage,
+        user: &User,
+        password: Option<&str>,
+    ) -> Result<(), SignInError> {
+        let Some(password_hash) = &user.password_hash else {
+            return Ok(());
+        };
+
+        let now = Utc::now().naive_utc();
+        if let Some(locked_until) = user.locked_until.filter(|until| *until > now) {
+            return Err(SignInError::LockedOut(locked_until));
+        }
+        let Some(password) = password else {
+            return Err(SignInError::PasswordRequired);
+        };
+
+        if verify_password(password_hash, password) {
+            if user.failed_sign_ins > 0 || user.locked_until.is_some() {
+                storage.save_failed_sign_ins(&user.id, &0, None);
+            }
+            return Ok(());
+        }
+
+        let failed_sign_ins = user.failed_sign_ins + 1;
+        if failed_sign_ins >= MAX_FAILED_SIGN_INS {
+            let locked_until = now + LOCKOUT_DURATION;
+            storage.save_failed_sign_ins(&user.id, &0, Some(&locked_until));
+            Err(SignInError::LockedOut(locked_until))
+        } else {
+            storage.save_failed_sign_ins(&user.id, &failed_sign_ins, None);
+            Err(SignInError::WrongPassword)
+        }
+    }
+
+    /// Users with a password can only be renamed or merged by themselves
+    fn check_may_change_user(
+        &self,
+        storage: &mut dyn Storage,
+        username: &str,
+    ) -> Result<(), String> {
+        if self.get_username().as_deref() == Some(username)
+            || !storage.get_users().iter().any(|name| name == username)
+        {
+            return Ok(());
+        }
+
+        match storage.get_or_create_user(username).password_hash {
+            Some(_) => Err(format!(
+                "{} has a password, only they can change their user",
+                username
+            )),
+            None => Ok(()),
+        }
+    }
+
+    pub fn set_ignore_name_case(&mut self, ignore_name_case: bool) {
+        self.ignore_name_case = ignore_name_case;
+    }
+
+    /// Rename a user, keeping their history. Only while the timer is idle.
+    pub fn rename_user(&mut self, old_name: &str, new_name: &str) -> Result<(), String> {
+        if self.get_state() != Idle {
+            return Err(String::from("Stop the timer before changing users"));
+        }
+
+        let mut storage = PomodoroTimer::lock_storage(&self.storage);
+        self.check_may_change_user(&mut *storage, old_name)?;
+        storage.rename_user(old_name, new_name)?;
+
+        if let Some(user) = self.user.as_mut().filter(|user| user.name == old_name) {
+            user.name = new_name.to_string();
+        }
+        Ok(())
+    }
+
+    /// Move the history of one user to another and delete the first one. Only while the
+    /// timer is idle. Someone signed in as the deleted user is signed in as the other one.
+    pub fn merge_users(&mut self, from_name: &str, into_name: &str) -> Result<(), String> {
+        if self.get_state() != Idle {
+            return Err(String::from("Stop the timer before changing users"));
+        }
+
+        let mut storage = PomodoroTimer::lock_storage(&self.storage);
+        self.check_may_change_user(&mut *storage, from_name)?;
+        self.check_may_change_user(&mut *storage, into_name)?;
+        storage.merge_users(from_name, into_name)?;
+
+        if self.get_username().as_deref() == Some(from_name) {
+            self.user = Some(storage.get_or_create_user(into_name));
+            self.task = None;
+            drop(storage);
+            self.apply_user_settings();
+        }
+        Ok(())
+    }
+
     pub fn is_user_signed_in(&self) -> bool {
-        self.username.is_some()
+        self.user.is_some()
     }
 
     pub fn get_username(&self) -> Option<String> {
-        self.username.clone()
+        self.user.as_ref().map(|user| user.name.clone())
+    }
+
+    fn get_user_id(&self) -> Option<i32> {
+        self.user.as_ref().map(|user| user.id)
     }
 
     pub fn get_users(&self) -> Vec<String> {
-        let connection = &mut establish_connection();
-        get_users(connection)
+        let mut storage = PomodoroTimer::lock_storage(&self.storage);
+        storage.get_users()
     }
 }
@@ -1,11 +1,58 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    tags (id) {
+        id -> Integer,
+        name -> Text,
+    }
+}
+
+diesel::table! {
+    tasks (id) {
+        id -> Integer,
+        user_id -> Integer,
+        name -> Text,
+        estimated_pomodoros -> Integer,
+        created -> Date,
+    }
+}
+
+diesel::table! {
+    timer_run_tags (timer_run_id, tag_id) {
+        timer_run_id -> Integer,
+        tag_id -> Integer,
+    }
+}
+
 diesel::table! {
     timer_runs (id) {
         id -> Integer,
-        user -> Text,
+        user_id -> Integer,
         working_time_secs -> Integer,
         breaking_time_secs -> Integer,
         date -> Date,
+        note -> Nullable<Text>,
+        task_id -> Nullable<Integer>,
+        uuid -> Text,
+    }
+}
+
+diesel::table! {
+    users (id) {
+        id -> Integer,
+        name -> Text,
+        created_at -> Timestamp,
+        settings -> Nullable<Text>,
+        password_hash -> Nullable<Text>,
+        failed_sign_ins -> Integer,
+        locked_until -> Nullable<Timestamp>,
     }
 }
+
+diesel::joinable!(tasks -> users (user_id));
+diesel::joinable!(timer_run_tags -> tags (tag_id));
+diesel::joinable!(timer_run_tags -> timer_runs (timer_run_id));
+diesel::joinable!(timer_runs -> tasks (task_id));
+diesel::joinable!(timer_runs -> users (user_id));
+
+diesel::allow_tables_to_appear_in_same_query!(tags, tasks, timer_run_tags, timer_runs, users,);
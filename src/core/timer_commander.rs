@@ -1,10 +1,17 @@
 use std::sync::mpsc::Sender;
+use std::time::Duration;
 
 #[derive(Debug, PartialEq)]
 pub enum TimerCommand {
     Pause,
     Start,
     Stop,
+    /// End the current phase right away
+    Skip,
+    /// Make the current phase longer
+    Extend(Duration),
+    /// Make the current phase shorter, ending it when there is less time left
+    Shorten(Duration),
     GetTimeRemaining,
 }
 
@@ -29,10 +36,21 @@ impl TimerCommander {
         self.command_sender.send(TimerCommand::Start).unwrap();
     }
 
+    pub fn skip_phase(&mut self) {
+        self.command_sender.send(TimerCommand::Skip).unwrap();
+    }
+
+    pub fn extend_phase(&mut self, by: Duration) {
+        self.command_sender.send(TimerCommand::Extend(by)).unwrap();
+    }
+
+    pub fn shorten_phase(&mut self, by: Duration) {
+        self.command_sender.send(TimerCommand::Shorten(by)).unwrap();
+    }
+
     pub fn get_time_remaining(&self) -> bool {
-        match self.command_sender.send(TimerCommand::GetTimeRemaining) {
-            Ok(_) => true,
-            Err(_) => false,
-        }
+        self.command_sender
+            .send(TimerCommand::GetTimeRemaining)
+            .is_ok()
     }
 }
@@ -4,9 +4,10 @@ use std::sync::mpsc::{Receiver, Sender};
 use std::thread;
 use std::time::{Duration, Instant};
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Debug)]
 pub enum ExitCondition {
-    Ok,
+    /// The phase is over after running this long, pauses not counted
+    Ok(Duration),
     Terminated,
 }
 
@@ -23,15 +24,16 @@ impl TimerRunner {
         }
     }
 
-    pub fn run_timer(&mut self, duration: Duration) -> ExitCondition {
+    pub fn run_timer(&mut self, mut duration: Duration) -> ExitCondition {
         // Start the timer
         let start_time = Instant::now();
         let mut time_in_pause = Duration::new(0, 0);
+        let running = |time_in_pause: Duration| start_time.elapsed().saturating_sub(time_in_pause);
 
-        while start_time.elapsed() < duration + time_in_pause {
+        while running(time_in_pause) < duration {
             thread::sleep(Duration::from_millis(10));
 
-            let remaining = (duration + time_in_pause).saturating_sub(start_time.elapsed());
+            let remaining = duration.saturating_sub(running(time_in_pause));
 
             // Check for new commands
             let command = self.command_receiver.try_recv();
@@ -41,30 +43,45 @@ impl TimerRunner {
                     TimerCommand::Start => continue,
                     TimerCommand::Pause => {
                         let start_pause = Instant::now();
-                        if self.wait_for_resume(remaining) == Stop {
-                            return ExitCondition::Terminated;
-                        };
+                        let resumed_with = self.wait_for_resume(&mut duration, remaining);
                         time_in_pause += start_pause.elapsed();
+                        match resumed_with {
+                            Stop => return ExitCondition::Terminated,
+                            TimerCommand::Skip => break,
+                            _ => {}
+                        }
                     }
                     Stop => return ExitCondition::Terminated,
+                    TimerCommand::Skip => break,
+                    TimerCommand::Extend(by) => duration = duration.saturating_add(by),
+                    TimerCommand::Shorten(by) => {
+                        // Never shorter than the time already run, which ends it now
+                        duration = duration.saturating_sub(by).max(running(time_in_pause))
+                    }
                     TimerCommand::GetTimeRemaining => self.time_sender.send(remaining).unwrap(),
                 }
             }
         }
 
-        ExitCondition::Ok
+        ExitCondition::Ok(running(time_in_pause).min(duration))
     }
 
-    fn wait_for_resume(&mut self, remaining: Duration) -> TimerCommand {
+    /// Wait while paused, still extending or shortening the phase. Returns the command
+    /// that ended the pause.
+    fn wait_for_resume(&mut self, duration: &mut Duration, remaining: Duration) -> TimerCommand {
+        let running = duration.saturating_sub(remaining);
         while let Ok(command) = self.command_receiver.recv() {
             match command {
                 TimerCommand::Pause => continue,
-                TimerCommand::Start => return TimerCommand::Start,
+                TimerCommand::Extend(by) => *duration = duration.saturating_add(by),
+                TimerCommand::Shorten(by) => *duration = duration.saturating_sub(by).max(running),
                 TimerCommand::GetTimeRemaining => {
-                    self.time_sender.send(remaining).unwrap();
+                    self.time_sender
+                        .send(duration.saturating_sub(running))
+                        .unwrap();
                     continue;
                 }
-                Stop => return Stop,
+                TimerCommand::Start | TimerCommand::Skip | Stop => return command,
             }
         }
         Stop
@@ -0,0 +1,186 @@
+use crate::db::storage::Storage;
+use crate::db::timer_database::MIGRATIONS;
+use chrono::{Days, NaiveDate};
+use diesel::migration::MigrationSource;
+use diesel::prelude::*;
+use diesel::sql_types::Text;
+use diesel::sqlite::Sqlite;
+use diesel_migrations::MigrationHarness;
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(QueryableByName)]
+struct IntegrityCheck {
+    #[diesel(sql_type = Text)]
+    integrity_check: String,
+}
+
+/// Write a consistent snapshot of the database to `path`, which must not exist yet.
+/// `VACUUM INTO` reads the database in one transaction, so the app can keep using it.
+pub fn backup_database(conn: &mut SqliteConnection, path: &Path) -> io::Result<()> {
+    if path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", path.display()),
+        ));
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    diesel::sql_query("VACUUM INTO ?")
+        .bind::<Text, _>(path.to_string_lossy())
+        .execute(conn)
+        .map_err(io::Error::other)?;
+
+    Ok(())
+}
+
+/// Replace the database at `database_path` with the backup at `backup_path`. The backup is
+/// checked and migrated on a copy first, and the current database is kept next to it with a
+/// `.before-restore` suffix, so a failed restore leaves everything as it was.
+/// The connection is closed before the file is swapped, reconnect to `database_path` afterwards.
+pub fn restore_database(
+    conn: SqliteConnection,
+    database_path: &Path,
+    backup_path: &Path,
+) -> io::Result<()> {
+    if !backup_path.is_file() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} does not exist", backup_path.display()),
+        ));
+    }
+
+    let restoring = with_suffix(database_path, "restoring");
+    fs::copy(backup_path, &restoring)?;
+    let swapped = swap_in_backup(conn, database_path, &restoring);
+    if swapped.is_err() && restoring.exists() {
+        fs::remove_file(&restoring)?;
+    }
+    swapped
+}
+
+/// Keep the current database as `.before-restore` and move the prepared copy in its place
+fn swap_in_backup(
+    mut conn: SqliteConnection,
+    database_path: &Path,
+    restoring: &Path,
+) -> io::Result<()> {
+    prepare_backup(restoring)?;
+
+    let previous = with_suffix(database_path, "before-restore");
+    if previous.exists() {
+        fs::remove_file(&previous)?;
+    }
+    backup_database(&mut conn, &previous)?;
+
+    drop(conn);
+    fs::rename(restoring, database_path)
+}
+
+/// Check that the file is an intact database of this app, no newer than this version,
+/// and run the migrations it is missing
+pub(crate) fn prepare_backup(path: &Path) -> io::Result<()> {
+    let invalid = |reason: String| io::Error::new(io::ErrorKind::InvalidData, reason);
+    let not_a_backup = || {
+        invalid(format!(
+            "{} is not a pomodorotimer database",
+            path.display()
+        ))
+    };
+
+    let mut conn =
+        SqliteConnection::establish(&path.to_string_lossy()).map_err(|_| not_a_backup())?;
+
+    let checks: Vec<IntegrityCheck> = diesel::sql_query("PRAGMA integrity_check")
+        .load(&mut conn)
+        .map_err(|_| not_a_backup())?;
+    if checks.iter().any(|check| check.integrity_check != "ok") {
+        return Err(invalid(format!("{} is damaged", path.display())));
+    }
+
+    let applied = conn.applied_migrations().map_err(|_| not_a_backup())?;
+    if applied.is_empty() {
+        return Err(not_a_backup());
+    }
+    let known: HashSet<String> = MigrationSource::<Sqlite>::migrations(&MIGRATIONS)
+        .map_err(|e| invalid(e.to_string()))?
+        .iter()
+        .map(|migration| migration.name().version().to_string())
+        .collect();
+    if applied
+        .iter()
+        .any(|version| !known.contains(&version.to_string()))
+    {
+        return Err(invalid(format!(
+            "{} was made by a newer version of pomodorotimer",
+            path.display()
+        )));
+    }
+
+    conn.run_pending_migrations(MIGRATIONS)
+        .map_err(|e| invalid(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Directory the daily backups of a database go to
+pub fn backup_dir(database_path: &Path) -> PathBuf {
+    database_path
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join("backups")
+}
+
+/// Make today's backup, named like `pomodoro-2026-10-18.db` in `backup_dir`, unless there
+/// already is one, and delete the daily backups older than `keep_days` days.
+/// Returns the path of the new backup.
+pub fn rotate_daily_backups(
+    storage: &mut dyn Storage,
+    database_path: &Path,
+    today: NaiveDate,
+    keep_days: u32,
+) -> io::Result<Option<PathBuf>> {
+    let dir = backup_dir(database_path);
+    let stem = database_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let name_for = |date: NaiveDate| format!("{}-{}.db", stem, date.format("%Y-%m-%d"));
+
+    let todays_backup = dir.join(name_for(today));
+    let created = if todays_backup.exists() {
+        None
+    } else {
+        storage.backup(&todays_backup)?;
+        Some(todays_backup)
+    };
+
+    let oldest_kept = today
+        .checked_sub_days(Days::new(keep_days.saturating_sub(1).into()))
+        .unwrap_or(NaiveDate::MIN);
+    for entry in fs::read_dir(&dir)? {
+        let file_name = entry?.file_name().to_string_lossy().into_owned();
+        let date = file_name
+            .strip_prefix(&format!("{}-", stem))
+            .and_then(|rest| rest.strip_suffix(".db"))
+            .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok());
+
+        if date.is_some_and(|date| date < oldest_kept) {
+            fs::remove_file(dir.join(file_name))?;
+        }
+    }
+
+    Ok(created)
+}
+
+/// `pomodoro.db` with suffix `restoring` is `pomodoro.db.restoring`
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".");
+    file_name.push(suffix);
+    path.with_file_name(file_name)
+}
@@ -0,0 +1,395 @@
+use crate::core::models::{
+    GroupedRunTotals, NewTimerRun, RunRecord, RunTotals, Task, TimerRuns, User, UserSettings,
+};
+use crate::db::storage::{normalize_tag, normalize_tags, Storage};
+use crate::db::timer_database::Grouping;
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use std::collections::{BTreeMap, HashSet};
+
+/// Storage that only lives as long as the value, for tests and for embedding the timer
+/// without a database
+#[derive(Default)]
+pub struct MemoryStorage {
+    users: Vec<User>,
+    timer_runs: Vec<(TimerRuns, Vec<String>)>,
+    tasks: Vec<Task>,
+    /// Last ids handed out, so ids of deleted rows are not reused, like `AUTOINCREMENT`
+    last_user_id: i32,
+    last_timer_run_id: i32,
+    last_task_id: i32,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        MemoryStorage::default()
+    }
+
+    /// The user's runs, optionally only those within the inclusive date range or with a tag
+    fn timer_runs<'a>(
+        &'a self,
+        user_id: &'a i32,
+        date_range: Option<(NaiveDate, NaiveDate)>,
+        tag: Option<&'a str>,
+    ) -> impl Iterator<Item = &'a (TimerRuns, Vec<String>)> {
+        self.timer_runs.iter().filter(move |(tr, tags)| {
+            tr.user_id == *user_id
+                && date_range.is_none_or(|(from, to)| from <= tr.date && tr.date <= to)
+                && tag.is_none_or(|tag| tags.contains(&normalize_tag(tag)))
+        })
+    }
+
+    fn add_to_totals(totals: &mut RunTotals, tr: &TimerRuns) {
+        totals.working_time_secs += tr.working_time_secs as i64;
+        totals.breaking_time_secs += tr.breaking_time_secs as i64;
+        totals.runs += 1;
+    }
+
+    fn into_grouped(groups: BTreeMap<String, RunTotals>) -> Vec<GroupedRunTotals> {
+        groups
+            .into_iter()
+            .map(|(key, totals)| GroupedRunTotals { key, totals })
+            .collect()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn insert_timer_run(&mut self, new_run: &NewTimerRun, tags: &[String]) -> i32 {
+        if self
+            .timer_runs
+            .iter()
+            .any(|(tr, _)| tr.uuid == new_run.uuid)
+        {
+            panic!("Error saving new timer run: uuid {} exists", new_run.uuid);
+        }
+
+        self.last_timer_run_id += 1;
+        let id = self.last_timer_run_id;
+        let tags = normalize_tags(tags);
+
+        self.timer_runs.push((
+            TimerRuns {
+                id,
+                user_id: *new_run.user_id,
+                working_time_secs: *new_run.working_time_secs,
+                breaking_time_secs: *new_run.breaking_time_secs,
+                date: *new_run.date,
+                note: new_run.note.map(String::from),
+                task_id: new_run.task_id.copied(),
+                uuid: new_run.uuid.to_string(),
+            },
+            tags,
+        ));
+        id
+    }
+
+    fn set_timer_run_note(&mut self, timer_run_id: &i32, note: &str) {
+        if let Some((tr, _)) = self
+            .timer_runs
+            .iter_mut()
+            .find(|(tr, _)| tr.id == *timer_run_id)
+        {
+            tr.note = Some(note.to_string());
+        }
+    }
+
+    fn get_timer_run(&mut self, timer_run_id: &i32) -> Option<(TimerRuns, Vec<String>)> {
+        self.timer_runs
+            .iter()
+            .find(|(tr, _)| tr.id == *timer_run_id)
+            .cloned()
+    }
+
+    fn update_timer_run(&mut self, timer_run: &TimerRuns) {
+        if let Some((tr, _)) = self
+            .timer_runs
+            .iter_mut()
+            .find(|(tr, _)| tr.id == timer_run.id)
+        {
+            *tr = timer_run.clone();
+        }
+    }
+
+    fn delete_timer_run(&mut self, timer_run_id: &i32) -> bool {
+        let runs = self.timer_runs.len();
+        self.timer_runs.retain(|(tr, _)| tr.id != *timer_run_id);
+        self.timer_runs.len() < runs
+    }
+
+    fn reinsert_timer_run(&mut self, timer_run: &TimerRuns, tags: &[String]) {
+        let tags = normalize_tags(tags);
+
+        // Keep the runs sorted by id
+        let index = self
+            .timer_runs
+            .partition_point(|(tr, _)| tr.id < timer_run.id);
+        self.timer_runs.insert(index, (timer_run.clone(), tags));
+    }
+
+    fn get_last_timer_run_id(&mut self, user_id: &i32) -> Option<i32> {
+        self.timer_runs(user_id, None, None)
+            .map(|(tr, _)| tr.id)
+            .max()
+    }
+
+    fn get_timer_run_uuids(&mut self) -> HashSet<String> {
+        self.timer_runs
+            .iter()
+            .map(|(tr, _)| tr.uuid.clone())
+            .collect()
+    }
+
+    fn get_run_records(
+        &mut self,
+        user_id: &i32,
+        date_range: Option<(NaiveDate, NaiveDate)>,
+    ) -> Vec<RunRecord> {
+        let username = self
+            .users
+            .iter()
+            .find(|user| user.id == *user_id)
+            .map(|user| user.name.clone())
+            .unwrap_or_default();
+
+        self.timer_runs(user_id, date_range, None)
+            .map(|(tr, tags)| RunRecord {
+                id: tr.id,
+                date: tr.date,
+                user: username.clone(),
+                working_time_secs: tr.working_time_secs,
+                breaking_time_secs: tr.breaking_time_secs,
+                tags: tags.clone(),
+                task: self
+                    .tasks
+                    .iter()
+                    .find(|task| Some(task.id) == tr.task_id)
+                    .map(|task| task.name.clone()),
+                note: tr.note.clone(),
+                uuid: tr.uuid.clone(),
+            })
+            .collect()
+    }
+
+    fn sum_timer_runs(
+        &mut self,
+        user_id: &i32,
+        date_range: Option<(NaiveDate, NaiveDate)>,
+        tag: Option<&str>,
+    ) -> RunTotals {
+        let mut totals = RunTotals::default();
+        for (tr, _) in self.timer_runs(user_id, date_range, tag) {
+            MemoryStorage::add_to_totals(&mut totals, tr);
+        }
+        totals
+    }
+
+    fn get_tag_run_totals(
+        &mut self,
+        user_id: &i32,
+        date_range: Option<(NaiveDate, NaiveDate)>,
+    ) -> Vec<GroupedRunTotals> {
+        let mut groups: BTreeMap<String, RunTotals> = BTreeMap::new();
+        for (tr, tags) in self.timer_runs(user_id, date_range, None) {
+            for tag in tags {
+                MemoryStorage::add_to_totals(groups.entry(tag.clone()).or_default(), tr);
+            }
+        }
+        MemoryStorage::into_grouped(groups)
+    }
+
+    fn get_task_run_totals(
+        &mut self,
+        user_id: &i32,
+        date_range: Option<(NaiveDate, NaiveDate)>,
+    ) -> Vec<GroupedRunTotals> {
+        let mut groups: BTreeMap<String, RunTotals> = BTreeMap::new();
+        for (tr, _) in self.timer_runs(user_id, date_range, None) {
+            if let Some(task) = self.tasks.iter().find(|task| Some(task.id) == tr.task_id) {
+                MemoryStorage::add_to_totals(groups.entry(task.name.clone()).or_default(), tr);
+            }
+        }
+        MemoryStorage::into_grouped(groups)
+    }
+
+    fn get_grouped_run_totals(
+        &mut self,
+        user_id: &i32,
+        date_range: Option<(NaiveDate, NaiveDate)>,
+        grouping: Grouping,
+    ) -> Vec<GroupedRunTotals> {
+        let mut groups: BTreeMap<String, RunTotals> = BTreeMap::new();
+        for (tr, _) in self.timer_runs(user_id, date_range, None) {
+            let key = grouping.key(tr.date);
+            MemoryStorage::add_to_totals(groups.entry(key).or_default(), tr);
+        }
+        MemoryStorage::into_grouped(groups)
+    }
+
+    fn get_users(&mut self) -> Vec<String> {
+        let mut names: Vec<String> = self.users.iter().map(|user| user.name.clone()).collect();
+        names.sort();
+        names
+    }
+
+    fn get_user(&mut self, username: &str) -> Option<User> {
+        self.users
+            .iter()
+            .find(|user| user.name == username)
+            .cloned()
+    }
+
+    fn get_or_create_user(&mut self, username: &str) -> User {
+        if let Some(user) = self.get_user(username) {
+            return user;
+        }
+
+        self.last_user_id += 1;
+        let user = User {
+            id: self.last_user_id,
+            name: username.to_string(),
+            created_at: Utc::now().naive_utc(),
+            settings: None,
+            password_hash: None,
+            failed_sign_ins: 0,
+            locked_until: None,
+        };
+        self.users.push(user.clone());
+        user
+    }
+
+    fn save_user_settings(&mut self, user_id: &i32, settings: &UserSettings) {
+        if let Some(user) = self.users.iter_mut().find(|user| user.id == *user_id) {
+            user.settings = Some(serde_json::to_string(settings).expect("Error writing settings"));
+        }
+    }
+
+    fn save_password_hash(&mut self, user_id: &i32, password_hash: Option<&str>) {
+        if let Some(user) = self.users.iter_mut().find(|user| user.id == *user_id) {
+            user.password_hash = password_hash.map(String::from);
+            user.failed_sign_ins = 0;
+            user.locked_until = None;
+        }
+    }
+
+    fn save_failed_sign_ins(
+        &mut self,
+        user_id: &i32,
+        failed_sign_ins: &i32,
+        locked_until: Option<&NaiveDateTime>,
+    ) {
+        if let Some(user) = self.users.iter_mut().find(|user| user.id == *user_id) {
+            user.failed_sign_ins = *failed_sign_ins;
+            user.locked_until = locked_until.copied();
+        }
+    }
+
+    fn rename_user(&mut self, old_name: &str, new_name: &str) -> Result<(), String> {
+        let index = self
+            .users
+            .iter()
+            .position(|user| user.name == old_name)
+            .ok_or_else(|| format!("There is no user {}", old_name))?;
+        if self.users.iter().any(|user| user.name == new_name) {
+            return Err(format!(
+                "{} already exists, merge the users instead",
+                new_name
+            ));
+        }
+
+        self.users[index].name = new_name.to_string();
+        Ok(())
+    }
+
+    fn merge_users(&mut self, from_name: &str, into_name: &str) -> Result<(), String> {
+        let user_id = |name: &str| {
+            self.users
+                .iter()
+                .find(|user| user.name == name)
+                .map(|user| user.id)
+                .ok_or_else(|| format!("There is no user {}", name))
+        };
+        let from = user_id(from_name)?;
+        let into = user_id(into_name)?;
+        if from == into {
+            return Err(String::from("Cannot merge a user into themselves"));
+        }
+
+        // Tasks both users have become the second user's task
+        let mut merged_tasks = BTreeMap::new();
+        for task in self.tasks.iter().filter(|task| task.user_id == from) {
+            if let Some(into_task) = self
+                .tasks
+                .iter()
+                .find(|t| t.user_id == into && t.name == task.name)
+            {
+                merged_tasks.insert(task.id, into_task.id);
+            }
+        }
+        self.tasks
+            .retain(|task| !merged_tasks.contains_key(&task.id));
+        for task in self.tasks.iter_mut().filter(|task| task.user_id == from) {
+            task.user_id = into;
+        }
+
+        for (tr, _) in self.timer_runs.iter_mut() {
+            if tr.user_id == from {
+                tr.user_id = into;
+            }
+            if let Some(task_id) = tr.task_id.and_then(|task_id| merged_tasks.get(&task_id)) {
+                tr.task_id = Some(*task_id);
+            }
+        }
+
+        self.users.retain(|user| user.id != from);
+        Ok(())
+    }
+
+    fn save_task(
+        &mut self,
+        user_id: &i32,
+        task_name: &str,
+        estimated_pomodoros: &i32,
+        created: &NaiveDate,
+    ) -> Task {
+        if let Some(task) = self
+            .tasks
+            .iter_mut()
+            .find(|task| task.user_id == *user_id && task.name == task_name)
+        {
+            task.estimated_pomodoros = *estimated_pomodoros;
+            return task.clone();
+        }
+
+        self.last_task_id += 1;
+        let task = Task {
+            id: self.last_task_id,
+            user_id: *user_id,
+            name: task_name.to_string(),
+            estimated_pomodoros: *estimated_pomodoros,
+            created: *created,
+        };
+        self.tasks.push(task.clone());
+        task
+    }
+
+    fn get_task(&mut self, user_id: &i32, task_name: &str) -> Option<Task> {
+        self.tasks
+            .iter()
+            .find(|task| task.user_id == *user_id && task.name == task_name)
+            .cloned()
+    }
+
+    fn get_tasks_with_actuals(&mut self, user_id: &i32) -> Vec<(Task, i64)> {
+        self.tasks
+            .iter()
+            .filter(|task| task.user_id == *user_id)
+            .map(|task| {
+                let actual = self
+                    .timer_runs
+                    .iter()
+                    .filter(|(tr, _)| tr.task_id == Some(task.id))
+                    .count();
+                (task.clone(), actual as i64)
+            })
+            .collect()
+    }
+}
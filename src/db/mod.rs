@@ -1 +1,8 @@
+pub mod backup;
+pub mod memory_storage;
+#[cfg(feature = "postgres")]
+pub mod pg_storage;
+pub mod sqlite_storage;
+pub mod storage;
 pub mod timer_database;
+pub mod timer_import;
@@ -0,0 +1,228 @@
+use crate::core::models::{
+    GroupedRunTotals, NewTimerRun, RunRecord, RunTotals, Task, TimerRuns, User, UserSettings,
+};
+use crate::db::storage::Storage;
+use crate::db::timer_database::{
+    add_tags_to_timer_run, delete_timer_run, get_grouped_run_totals, get_last_timer_run_id,
+    get_or_create_user, get_run_records, get_tag_run_totals, get_task, get_task_run_totals,
+    get_tasks_with_actuals, get_timer_run, get_timer_run_tags, get_timer_run_uuids, get_user,
+    get_users, insert_timer_run, merge_users, normalize_stored_tags, reinsert_timer_run,
+    rename_user, save_failed_sign_ins, save_password_hash, save_task, save_user_settings,
+    set_timer_run_note, sum_timer_runs, update_timer_run, Dialect, Grouping,
+};
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::pg::Pg;
+use diesel::prelude::*;
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use std::collections::HashSet;
+
+/// Mirrors `MIGRATIONS` for PostgreSQL
+pub const PG_MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations_postgres");
+
+pub fn establish_pg_connection(database_url: &str) -> PgConnection {
+    let mut conn = PgConnection::establish(database_url)
+        .unwrap_or_else(|_| panic!("Error connecting to {}", database_url));
+
+    conn.run_pending_migrations(PG_MIGRATIONS)
+        .expect("Could not run migrations");
+    normalize_stored_tags(&mut conn);
+
+    conn
+}
+
+/// Storage in a PostgreSQL database, shared by everyone on the team, see `establish_pg_connection`
+pub struct PgStorage {
+    connection: PgConnection,
+}
+
+impl PgStorage {
+    pub fn new(connection: PgConnection) -> Self {
+        PgStorage { connection }
+    }
+}
+
+impl Storage for PgStorage {
+    fn insert_timer_run(&mut self, new_run: &NewTimerRun, tags: &[String]) -> i32 {
+        self.connection
+            .transaction::<_, diesel::result::Error, _>(|conn| {
+                let timer_run_id = insert_timer_run(conn, new_run);
+                add_tags_to_timer_run(conn, &timer_run_id, tags);
+                Ok(timer_run_id)
+            })
+            .expect("Error saving new timer run")
+    }
+
+    fn set_timer_run_note(&mut self, timer_run_id: &i32, note: &str) {
+        set_timer_run_note(&mut self.connection, timer_run_id, note)
+    }
+
+    fn get_timer_run(&mut self, timer_run_id: &i32) -> Option<(TimerRuns, Vec<String>)> {
+        let timer_run = get_timer_run(&mut self.connection, timer_run_id)?;
+        Some((
+            timer_run,
+            get_timer_run_tags(&mut self.connection, timer_run_id),
+        ))
+    }
+
+    fn update_timer_run(&mut self, timer_run: &TimerRuns) {
+        update_timer_run(&mut self.connection, timer_run)
+    }
+
+    fn delete_timer_run(&mut self, timer_run_id: &i32) -> bool {
+        self.connection
+            .transaction::<_, diesel::result::Error, _>(|conn| {
+                Ok(delete_timer_run(conn, timer_run_id))
+            })
+            .expect("Error deleting timer run")
+    }
+
+    fn reinsert_timer_run(&mut self, timer_run: &TimerRuns, tags: &[String]) {
+        self.connection
+            .transaction::<_, diesel::result::Error, _>(|conn| {
+                reinsert_timer_run(conn, timer_run, tags);
+                Ok(())
+            })
+            .expect("Error saving timer run")
+    }
+
+    fn get_last_timer_run_id(&mut self, user_id: &i32) -> Option<i32> {
+        get_last_timer_run_id(&mut self.connection, user_id)
+    }
+
+    fn get_timer_run_uuids(&mut self) -> HashSet<String> {
+        get_timer_run_uuids(&mut self.connection)
+    }
+
+    fn get_run_records(
+        &mut self,
+        user_id: &i32,
+        date_range: Option<(NaiveDate, NaiveDate)>,
+    ) -> Vec<RunRecord> {
+        get_run_records(&mut self.connection, user_id, date_range)
+    }
+
+    fn sum_timer_runs(
+        &mut self,
+        user_id: &i32,
+        date_range: Option<(NaiveDate, NaiveDate)>,
+        tag: Option<&str>,
+    ) -> RunTotals {
+        sum_timer_runs(&mut self.connection, user_id, date_range, tag)
+    }
+
+    fn get_tag_run_totals(
+        &mut self,
+        user_id: &i32,
+        date_range: Option<(NaiveDate, NaiveDate)>,
+    ) -> Vec<GroupedRunTotals> {
+        get_tag_run_totals(&mut self.connection, user_id, date_range)
+    }
+
+    fn get_task_run_totals(
+        &mut self,
+        user_id: &i32,
+        date_range: Option<(NaiveDate, NaiveDate)>,
+    ) -> Vec<GroupedRunTotals> {
+        get_task_run_totals(&mut self.connection, user_id, date_range)
+    }
+
+    fn get_grouped_run_totals(
+        &mut self,
+        user_id: &i32,
+        date_range: Option<(NaiveDate, NaiveDate)>,
+        grouping: Grouping,
+    ) -> Vec<GroupedRunTotals> {
+        get_grouped_run_totals(&mut self.connection, user_id, date_range, grouping)
+    }
+
+    fn get_users(&mut self) -> Vec<String> {
+        get_users(&mut self.connection)
+    }
+
+    fn get_user(&mut self, username: &str) -> Option<User> {
+        get_user(&mut self.connection, username)
+    }
+
+    fn get_or_create_user(&mut self, username: &str) -> User {
+        get_or_create_user(&mut self.connection, username)
+    }
+
+    fn save_user_settings(&mut self, user_id: &i32, settings: &UserSettings) {
+        save_user_settings(&mut self.connection, user_id, settings)
+    }
+
+    fn save_password_hash(&mut self, user_id: &i32, password_hash: Option<&str>) {
+        save_password_hash(&mut self.connection, user_id, password_hash)
+    }
+
+    fn save_failed_sign_ins(
+        &mut self,
+        user_id: &i32,
+        failed_sign_ins: &i32,
+        locked_until: Option<&NaiveDateTime>,
+    ) {
+        save_failed_sign_ins(&mut self.connection, user_id, failed_sign_ins, locked_until)
+    }
+
+    fn rename_user(&mut self, old_name: &str, new_name: &str) -> Result<(), String> {
+        rename_user(&mut self.connection, old_name, new_name)
+    }
+
+    fn merge_users(&mut self, from_name: &str, into_name: &str) -> Result<(), String> {
+        merge_users(&mut self.connection, from_name, into_name)
+    }
+
+    fn save_task(
+        &mut self,
+        user_id: &i32,
+        task_name: &str,
+        estimated_pomodoros: &i32,
+        created: &NaiveDate,
+    ) -> Task {
+        save_task(
+            &mut self.connection,
+            user_id,
+            task_name,
+            estimated_pomodoros,
+            created,
+        )
+    }
+
+    fn get_task(&mut self, user_id: &i32, task_name: &str) -> Option<Task> {
+        get_task(&mut self.connection, user_id, task_name)
+    }
+
+    fn get_tasks_with_actuals(&mut self, user_id: &i32) -> Vec<(Task, i64)> {
+        get_tasks_with_actuals(&mut self.connection, user_id)
+    }
+}
+
+impl Dialect for Pg {
+    fn group_key(grouping: Grouping) -> String {
+        match grouping {
+            Grouping::Day => String::from("to_char(timer_runs.date, 'YYYY-MM-DD')"),
+            // `DOW` counts from Sunday, so this is the number of days since the start of the week
+            Grouping::Week(week_start) => format!(
+                "to_char(timer_runs.date - \
+                 ((EXTRACT(DOW FROM timer_runs.date)::integer + {}) % 7), 'YYYY-MM-DD')",
+                7 - week_start.num_days_from_sunday()
+            ),
+            Grouping::Month => String::from("to_char(timer_runs.date, 'YYYY-MM')"),
+        }
+    }
+
+    /// PostgreSQL numbers its placeholders `$1`, `$2` and so on
+    fn placeholders(sql: &str) -> String {
+        let mut numbered = String::with_capacity(sql.len());
+        let mut number = 0;
+        for c in sql.chars() {
+            if c == '?' {
+                number += 1;
+                numbered.push_str(&format!("${}", number));
+            } else {
+                numbered.push(c);
+            }
+        }
+        numbered
+    }
+}
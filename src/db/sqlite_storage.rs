@@ -0,0 +1,221 @@
+use crate::core::models::{
+    GroupedRunTotals, NewTimerRun, RunRecord, RunTotals, Task, TimerRuns, User, UserSettings,
+};
+use crate::db::backup::{backup_database, restore_database};
+use crate::db::storage::Storage;
+use crate::db::timer_database::{
+    add_tags_to_timer_run, delete_timer_run, establish_connection, get_grouped_run_totals,
+    get_last_timer_run_id, get_or_create_user, get_run_records, get_tag_run_totals, get_task,
+    get_task_run_totals, get_tasks_with_actuals, get_timer_run, get_timer_run_tags,
+    get_timer_run_uuids, get_user, get_users, insert_timer_run, merge_users, reinsert_timer_run,
+    rename_user, save_failed_sign_ins, save_password_hash, save_task, save_user_settings,
+    set_timer_run_note, sum_timer_runs, update_timer_run, Grouping,
+};
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::prelude::*;
+use std::collections::HashSet;
+use std::io;
+use std::mem;
+use std::path::{Path, PathBuf};
+
+/// Storage in an SQLite database, see `establish_connection`
+pub struct SqliteStorage {
+    connection: SqliteConnection,
+    /// The database file, if it is known. Restoring needs it.
+    database_path: Option<PathBuf>,
+}
+
+impl SqliteStorage {
+    pub fn new(connection: SqliteConnection) -> Self {
+        SqliteStorage {
+            connection,
+            database_path: None,
+        }
+    }
+
+    /// Connect to the database file, creating and migrating it if needed
+    pub fn open(database_path: &Path) -> Self {
+        SqliteStorage {
+            connection: establish_connection(database_path),
+            database_path: Some(database_path.to_path_buf()),
+        }
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn insert_timer_run(&mut self, new_run: &NewTimerRun, tags: &[String]) -> i32 {
+        self.connection
+            .transaction::<_, diesel::result::Error, _>(|conn| {
+                let timer_run_id = insert_timer_run(conn, new_run);
+                add_tags_to_timer_run(conn, &timer_run_id, tags);
+                Ok(timer_run_id)
+            })
+            .expect("Error saving new timer run")
+    }
+
+    fn set_timer_run_note(&mut self, timer_run_id: &i32, note: &str) {
+        set_timer_run_note(&mut self.connection, timer_run_id, note)
+    }
+
+    fn get_timer_run(&mut self, timer_run_id: &i32) -> Option<(TimerRuns, Vec<String>)> {
+        let timer_run = get_timer_run(&mut self.connection, timer_run_id)?;
+        Some((
+            timer_run,
+            get_timer_run_tags(&mut self.connection, timer_run_id),
+        ))
+    }
+
+    fn update_timer_run(&mut self, timer_run: &TimerRuns) {
+        update_timer_run(&mut self.connection, timer_run)
+    }
+
+    fn delete_timer_run(&mut self, timer_run_id: &i32) -> bool {
+        self.connection
+            .transaction::<_, diesel::result::Error, _>(|conn| {
+                Ok(delete_timer_run(conn, timer_run_id))
+            })
+            .expect("Error deleting timer run")
+    }
+
+    fn reinsert_timer_run(&mut self, timer_run: &TimerRuns, tags: &[String]) {
+        self.connection
+            .transaction::<_, diesel::result::Error, _>(|conn| {
+                reinsert_timer_run(conn, timer_run, tags);
+                Ok(())
+            })
+            .expect("Error saving timer run")
+    }
+
+    fn get_last_timer_run_id(&mut self, user_id: &i32) -> Option<i32> {
+        get_last_timer_run_id(&mut self.connection, user_id)
+    }
+
+    fn get_timer_run_uuids(&mut self) -> HashSet<String> {
+        get_timer_run_uuids(&mut self.connection)
+    }
+
+    fn get_run_records(
+        &mut self,
+        user_id: &i32,
+        date_range: Option<(NaiveDate, NaiveDate)>,
+    ) -> Vec<RunRecord> {
+        get_run_records(&mut self.connection, user_id, date_range)
+    }
+
+    fn sum_timer_runs(
+        &mut self,
+        user_id: &i32,
+        date_range: Option<(NaiveDate, NaiveDate)>,
+        tag: Option<&str>,
+    ) -> RunTotals {
+        sum_timer_runs(&mut self.connection, user_id, date_range, tag)
+    }
+
+    fn get_tag_run_totals(
+        &mut self,
+        user_id: &i32,
+        date_range: Option<(NaiveDate, NaiveDate)>,
+    ) -> Vec<GroupedRunTotals> {
+        get_tag_run_totals(&mut self.connection, user_id, date_range)
+    }
+
+    fn get_task_run_totals(
+        &mut self,
+        user_id: &i32,
+        date_range: Option<(NaiveDate, NaiveDate)>,
+    ) -> Vec<GroupedRunTotals> {
+        get_task_run_totals(&mut self.connection, user_id, date_range)
+    }
+
+    fn get_grouped_run_totals(
+        &mut self,
+        user_id: &i32,
+        date_range: Option<(NaiveDate, NaiveDate)>,
+        grouping: Grouping,
+    ) -> Vec<GroupedRunTotals> {
+        get_grouped_run_totals(&mut self.connection, user_id, date_range, grouping)
+    }
+
+    fn get_users(&mut self) -> Vec<String> {
+        get_users(&mut self.connection)
+    }
+
+    fn get_user(&mut self, username: &str) -> Option<User> {
+        get_user(&mut self.connection, username)
+    }
+
+    fn get_or_create_user(&mut self, username: &str) -> User {
+        get_or_create_user(&mut self.connection, username)
+    }
+
+    fn save_user_settings(&mut self, user_id: &i32, settings: &UserSettings) {
+        save_user_settings(&mut self.connection, user_id, settings)
+    }
+
+    fn save_password_hash(&mut self, user_id: &i32, password_hash: Option<&str>) {
+        save_password_hash(&mut self.connection, user_id, password_hash)
+    }
+
+    fn save_failed_sign_ins(
+        &mut self,
+        user_id: &i32,
+        failed_sign_ins: &i32,
+        locked_until: Option<&NaiveDateTime>,
+    ) {
+        save_failed_sign_ins(&mut self.connection, user_id, failed_sign_ins, locked_until)
+    }
+
+    fn rename_user(&mut self, old_name: &str, new_name: &str) -> Result<(), String> {
+        rename_user(&mut self.connection, old_name, new_name)
+    }
+
+    fn merge_users(&mut self, from_name: &str, into_name: &str) -> Result<(), String> {
+        merge_users(&mut self.connection, from_name, into_name)
+    }
+
+    fn save_task(
+        &mut self,
+        user_id: &i32,
+        task_name: &str,
+        estimated_pomodoros: &i32,
+        created: &NaiveDate,
+    ) -> Task {
+        save_task(
+            &mut self.connection,
+            user_id,
+            task_name,
+            estimated_pomodoros,
+            created,
+        )
+    }
+
+    fn get_task(&mut self, user_id: &i32, task_name: &str) -> Option<Task> {
+        get_task(&mut self.connection, user_id, task_name)
+    }
+
+    fn get_tasks_with_actuals(&mut self, user_id: &i32) -> Vec<(Task, i64)> {
+        get_tasks_with_actuals(&mut self.connection, user_id)
+    }
+
+    fn backup(&mut self, path: &Path) -> io::Result<()> {
+        backup_database(&mut self.connection, path)
+    }
+
+    fn restore(&mut self, path: &Path) -> io::Result<()> {
+        let Some(database_path) = self.database_path.clone() else {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Only a database file can be restored",
+            ));
+        };
+
+        // The file is swapped while nothing has it open, then opened again whether or not
+        // the restore worked
+        let in_memory =
+            SqliteConnection::establish(":memory:").expect("Error opening an in-memory database");
+        let connection = mem::replace(&mut self.connection, in_memory);
+        let restored = restore_database(connection, &database_path, path);
+        self.connection = establish_connection(&database_path);
+        restored
+    }
+}
@@ -0,0 +1,154 @@
+use crate::core::models::{
+    GroupedRunTotals, NewTimerRun, RunRecord, RunTotals, Task, TimerRuns, User, UserSettings,
+};
+use crate::db::timer_database::Grouping;
+use chrono::{NaiveDate, NaiveDateTime};
+use std::collections::HashSet;
+use std::io;
+use std::path::Path;
+
+/// Everything the timer stores or looks up. Implemented by `SqliteStorage` for the database
+/// and by `MemoryStorage` for tests and embedding.
+pub trait Storage: Send {
+    /// Save a timer run with its tags, returning its id
+    fn insert_timer_run(&mut self, new_run: &NewTimerRun, tags: &[String]) -> i32;
+
+    /// Set or replace the note of a timer run
+    fn set_timer_run_note(&mut self, timer_run_id: &i32, note: &str);
+
+    /// Get a timer run with its tags, sorted
+    fn get_timer_run(&mut self, timer_run_id: &i32) -> Option<(TimerRuns, Vec<String>)>;
+
+    /// Replace the timer run having the same id with the given one
+    fn update_timer_run(&mut self, timer_run: &TimerRuns);
+
+    /// Delete a timer run and its tags, returning whether there was such a run
+    fn delete_timer_run(&mut self, timer_run_id: &i32) -> bool;
+
+    /// Save a deleted timer run again as it was, keeping its id
+    fn reinsert_timer_run(&mut self, timer_run: &TimerRuns, tags: &[String]);
+
+    /// Get the id of the user's most recently recorded timer run
+    fn get_last_timer_run_id(&mut self, user_id: &i32) -> Option<i32>;
+
+    /// The uuids of all timer runs of all users
+    fn get_timer_run_uuids(&mut self) -> HashSet<String>;
+
+    /// Get the user's runs with their task and tags, optionally only those within the
+    /// inclusive date range. Sorted by id.
+    fn get_run_records(
+        &mut self,
+        user_id: &i32,
+        date_range: Option<(NaiveDate, NaiveDate)>,
+    ) -> Vec<RunRecord>;
+
+    /// Sum up the user's runs, optionally only those within the inclusive date range or with a tag
+    fn sum_timer_runs(
+        &mut self,
+        user_id: &i32,
+        date_range: Option<(NaiveDate, NaiveDate)>,
+        tag: Option<&str>,
+    ) -> RunTotals;
+
+    /// Sum up the user's runs per tag, sorted by tag name
+    fn get_tag_run_totals(
+        &mut self,
+        user_id: &i32,
+        date_range: Option<(NaiveDate, NaiveDate)>,
+    ) -> Vec<GroupedRunTotals>;
+
+    /// Sum up the user's runs per task, sorted by task name
+    fn get_task_run_totals(
+        &mut self,
+        user_id: &i32,
+        date_range: Option<(NaiveDate, NaiveDate)>,
+    ) -> Vec<GroupedRunTotals>;
+
+    /// Sum up the user's runs per day, week or month, sorted by date
+    fn get_grouped_run_totals(
+        &mut self,
+        user_id: &i32,
+        date_range: Option<(NaiveDate, NaiveDate)>,
+        grouping: Grouping,
+    ) -> Vec<GroupedRunTotals>;
+
+    /// Names of all users, sorted
+    fn get_users(&mut self) -> Vec<String>;
+
+    /// Look up the user with exactly the given name
+    fn get_user(&mut self, username: &str) -> Option<User>;
+
+    /// Look up the user with the given name, creating it if it does not exist
+    fn get_or_create_user(&mut self, username: &str) -> User;
+
+    /// Save the user's settings, replacing the ones saved before
+    fn save_user_settings(&mut self, user_id: &i32, settings: &UserSettings);
+
+    /// Set or remove the user's password hash, which also clears failed sign ins
+    fn save_password_hash(&mut self, user_id: &i32, password_hash: Option<&str>);
+
+    /// Save the number of wrong passwords entered for the user, and until when they are locked out
+    fn save_failed_sign_ins(
+        &mut self,
+        user_id: &i32,
+        failed_sign_ins: &i32,
+        locked_until: Option<&NaiveDateTime>,
+    );
+
+    /// Rename a user, keeping their runs and tasks. Fails if there is no user with the old name
+    /// or someone already has the new one.
+    fn rename_user(&mut self, old_name: &str, new_name: &str) -> Result<(), String>;
+
+    /// Move all runs and tasks of one user to another and delete the first one, in one go.
+    /// Tasks both users have are merged into the second user's task.
+    fn merge_users(&mut self, from_name: &str, into_name: &str) -> Result<(), String>;
+
+    /// Create a task for the user, created on the given day, or update its estimate if the user
+    /// already has a task with that name
+    fn save_task(
+        &mut self,
+        user_id: &i32,
+        task_name: &str,
+        estimated_pomodoros: &i32,
+        created: &NaiveDate,
+    ) -> Task;
+
+    fn get_task(&mut self, user_id: &i32, task_name: &str) -> Option<Task>;
+
+    /// Get the user's tasks together with the number of completed pomodoros for each
+    fn get_tasks_with_actuals(&mut self, user_id: &i32) -> Vec<(Task, i64)>;
+
+    /// Write a consistent snapshot of the whole database to a new file
+    fn backup(&mut self, _path: &Path) -> io::Result<()> {
+        Err(backups_unsupported())
+    }
+
+    /// Replace the whole database with a snapshot written by `backup`
+    fn restore(&mut self, _path: &Path) -> io::Result<()> {
+        Err(backups_unsupported())
+    }
+}
+
+/// Tags are stored trimmed and lowercase, so `Work` and `work ` are the same tag
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim().to_lowercase()
+}
+
+/// The tags as they are stored: normalized, sorted and without empty ones or duplicates
+pub fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut tags: Vec<String> = tags
+        .iter()
+        .map(|tag| normalize_tag(tag))
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+fn backups_unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Backups are only supported for SQLite database files",
+    )
+}
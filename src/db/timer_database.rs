@@ -1,65 +1,1045 @@
-use crate::core::models::{NewTimerRun, TimerRuns};
+use crate::core::models::{
+    GroupedRunTotals, NewTimerRun, RunRecord, RunTotals, Tag, Task, TimerRuns, User, UserSettings,
+};
+use crate::db::storage::{normalize_tag, normalize_tags};
 use chrono::prelude::*;
+use chrono::Days;
+use diesel::backend::Backend;
+use diesel::connection::LoadConnection;
 use diesel::prelude::*;
+use diesel::query_builder::{BoxedSqlQuery, QueryFragment, QueryId, SqlQuery};
+use diesel::result::DatabaseErrorKind;
+use diesel::serialize::ToSql;
+use diesel::sql_types::{BigInt, Date, Integer, Nullable, Text, Timestamp};
+use diesel::sqlite::Sqlite;
 use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
-use dotenvy::dotenv;
-use std::env;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::{env, fs, io};
+use uuid::Uuid;
 
 pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!();
 
-pub fn establish_connection() -> SqliteConnection {
-    dotenv().ok();
+/// How `get_grouped_run_totals` groups runs
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum Grouping {
+    /// Keys like `2026-10-18`
+    Day,
+    /// Keys like `2026-10-12`, the first day of weeks starting on the given weekday
+    Week(Weekday),
+    /// Keys like `2026-10`
+    Month,
+}
+
+impl Grouping {
+    /// Key of the group the day belongs to
+    pub fn key(&self, date: NaiveDate) -> String {
+        match self {
+            Grouping::Day => date.format("%Y-%m-%d").to_string(),
+            Grouping::Week(week_start) => {
+                let days_into_week = date.weekday().days_since(*week_start);
+                (date - Days::new(days_into_week as u64))
+                    .format("%Y-%m-%d")
+                    .to_string()
+            }
+            Grouping::Month => date.format("%Y-%m").to_string(),
+        }
+    }
+}
+
+/// What differs between the SQL of the databases the shared queries run on
+pub trait Dialect: Backend {
+    /// SQL expression for the `Grouping` keys of `timer_runs.date`, the same ones
+    /// `Grouping::key` gives
+    fn group_key(grouping: Grouping) -> String;
+
+    /// The query with its `?` placeholders written the way the database takes them
+    fn placeholders(sql: &str) -> String;
+}
+
+impl Dialect for Sqlite {
+    fn group_key(grouping: Grouping) -> String {
+        match grouping {
+            Grouping::Day => String::from("strftime('%Y-%m-%d', timer_runs.date)"),
+            // `%w` counts from Sunday, so this is the number of days since the start of the week
+            Grouping::Week(week_start) => format!(
+                "date(timer_runs.date, '-' || \
+                 ((CAST(strftime('%w', timer_runs.date) AS INTEGER) + {}) % 7) || ' days')",
+                7 - week_start.num_days_from_sunday()
+            ),
+            Grouping::Month => String::from("strftime('%Y-%m', timer_runs.date)"),
+        }
+    }
+
+    fn placeholders(sql: &str) -> String {
+        sql.to_string()
+    }
+}
+
+/// A value bound to a `?` placeholder of a shared query
+#[derive(Copy, Clone, Debug)]
+pub enum Param<'a> {
+    Integer(i32),
+    NullableInteger(Option<i32>),
+    Text(&'a str),
+    NullableText(Option<&'a str>),
+    Date(NaiveDate),
+    NullableTimestamp(Option<NaiveDateTime>),
+}
+
+/// Id of a row loaded with `DbConnection::load_rows`
+#[derive(QueryableByName)]
+pub struct RowId {
+    #[diesel(sql_type = Integer)]
+    pub id: i32,
+}
+
+/// A single text column, selected `AS value`
+#[derive(QueryableByName)]
+pub struct RowText {
+    #[diesel(sql_type = Text)]
+    pub value: String,
+}
+
+/// A task with its number of runs, as loaded for `get_tasks_with_actuals`
+#[derive(QueryableByName)]
+pub struct TaskRow {
+    #[diesel(embed)]
+    pub task: Task,
+    #[diesel(sql_type = BigInt)]
+    pub actual: i64,
+}
+
+/// A run with the names of its user and task, as loaded for `get_run_records`
+#[derive(QueryableByName)]
+pub struct RunRow {
+    #[diesel(embed)]
+    pub run: TimerRuns,
+    #[diesel(sql_type = Text)]
+    pub user_name: String,
+    #[diesel(sql_type = Nullable<Text>)]
+    pub task_name: Option<String>,
+}
+
+/// A tag of a run, as loaded for `get_run_records`
+#[derive(QueryableByName)]
+pub struct RunTag {
+    #[diesel(sql_type = Integer)]
+    pub timer_run_id: i32,
+    #[diesel(sql_type = Text)]
+    pub name: String,
+}
+
+/// A connection to SQLite or PostgreSQL, which the queries written in plain SQL run on
+pub trait DbConnection: Connection<Backend: Dialect> {
+    /// Run the query and load its rows, with the columns matched by name
+    fn load_rows<T>(&mut self, sql: &str, params: &[Param]) -> QueryResult<Vec<T>>
+    where
+        T: QueryableByName<Self::Backend> + 'static;
+
+    /// Run the statement, returning the number of rows it changed
+    fn execute_sql(&mut self, sql: &str, params: &[Param]) -> QueryResult<usize>;
+}
+
+impl<C, B> DbConnection for C
+where
+    C: LoadConnection<Backend = B>,
+    B: Dialect + 'static,
+    i32: ToSql<Integer, B>,
+    str: ToSql<Text, B>,
+    NaiveDate: ToSql<Date, B>,
+    NaiveDateTime: ToSql<Timestamp, B>,
+    for<'a> BoxedSqlQuery<'a, B, SqlQuery>: QueryFragment<B> + QueryId,
+{
+    fn load_rows<T>(&mut self, sql: &str, params: &[Param]) -> QueryResult<Vec<T>>
+    where
+        T: QueryableByName<B> + 'static,
+    {
+        bind_params(sql, params).load(self)
+    }
+
+    fn execute_sql(&mut self, sql: &str, params: &[Param]) -> QueryResult<usize> {
+        bind_params(sql, params).execute(self)
+    }
+}
 
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+fn bind_params<'a, B>(sql: &str, params: &[Param<'a>]) -> BoxedSqlQuery<'a, B, SqlQuery>
+where
+    B: Dialect,
+    i32: ToSql<Integer, B>,
+    str: ToSql<Text, B>,
+    NaiveDate: ToSql<Date, B>,
+    NaiveDateTime: ToSql<Timestamp, B>,
+{
+    params.iter().fold(
+        diesel::sql_query(B::placeholders(sql)).into_boxed(),
+        |query, param| match *param {
+            Param::Integer(value) => query.bind::<Integer, _>(value),
+            Param::NullableInteger(value) => query.bind::<Nullable<Integer>, _>(value),
+            Param::Text(value) => query.bind::<Text, _>(value),
+            Param::NullableText(value) => query.bind::<Nullable<Text>, _>(value),
+            Param::Date(value) => query.bind::<Date, _>(value),
+            Param::NullableTimestamp(value) => query.bind::<Nullable<Timestamp>, _>(value),
+        },
+    )
+}
+
+/// Column names of CSV exports, in order. Tags are separated by `;`.
+pub const CSV_COLUMNS: [&str; 9] = [
+    "id",
+    "date",
+    "user",
+    "working_time_secs",
+    "breaking_time_secs",
+    "tags",
+    "task",
+    "note",
+    "uuid",
+];
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    /// Pick the format from a `.csv` or `.json` file extension
+    pub fn from_path(path: &Path) -> Option<ExportFormat> {
+        match path.extension()?.to_str()? {
+            "csv" => Some(ExportFormat::Csv),
+            "json" => Some(ExportFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Work out where the database lives. The `--database` flag wins over the `DATABASE_URL`
+/// environment variable, which wins over `$XDG_DATA_HOME/pomodorotimer/pomodoro.db`.
+/// Without a data directory, like when `$HOME` is not set, it is `pomodoro.db` in the
+/// current directory.
+pub fn resolve_database_path(cli_database: Option<String>) -> PathBuf {
+    if let Some(path) = cli_database.or_else(|| env::var("DATABASE_URL").ok()) {
+        return PathBuf::from(path);
+    }
+
+    match dirs::data_dir() {
+        Some(data_dir) => data_dir.join("pomodorotimer").join("pomodoro.db"),
+        None => PathBuf::from("pomodoro.db"),
+    }
+}
+
+/// Whether the database is a `postgres://` or `postgresql://` URL rather than an SQLite file
+pub fn is_postgres_url(database: &Path) -> bool {
+    let database = database.to_string_lossy();
+    database.starts_with("postgres://") || database.starts_with("postgresql://")
+}
+
+pub fn establish_connection(database_path: &Path) -> SqliteConnection {
+    if let Some(parent) = database_path.parent() {
+        fs::create_dir_all(parent)
+            .unwrap_or_else(|_| panic!("Error creating directory {}", parent.display()));
+    }
+
+    let database_url = database_path.to_string_lossy();
     let mut conn = SqliteConnection::establish(&database_url)
         .unwrap_or_else(|_| panic!("Error connecting to {}", database_url));
 
     conn.run_pending_migrations(MIGRATIONS)
         .expect("Could not run migrations");
+    normalize_stored_tags(&mut conn);
 
     conn
 }
 
+/// Normalize the tags stored before tags were normalized, the way `normalize_tag` does. The `normalize_tags`
+/// migration can only lowercase ASCII in SQL, so tags like `Ærø` are left for this step,
+/// which runs after the migrations and merges them into the tag with the normalized name.
+pub fn normalize_stored_tags<C: DbConnection>(conn: &mut C)
+where
+    Tag: QueryableByName<C::Backend>,
+    RowId: QueryableByName<C::Backend>,
+{
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let stored: Vec<Tag> = conn.load_rows("SELECT * FROM tags", &[])?;
+        for tag in stored {
+            let normalized = normalize_tag(&tag.name);
+            if normalized == tag.name {
+                continue;
+            }
+
+            let same: Option<RowId> = conn
+                .load_rows(
+                    "SELECT id FROM tags WHERE name = ?",
+                    &[Param::Text(&normalized)],
+                )?
+                .pop();
+            match same {
+                Some(same) => {
+                    conn.execute_sql(
+                        "UPDATE timer_run_tags SET tag_id = ? WHERE tag_id = ? AND timer_run_id NOT IN \
+                         (SELECT timer_run_id FROM timer_run_tags WHERE tag_id = ?)",
+                        &[
+                            Param::Integer(same.id),
+                            Param::Integer(tag.id),
+                            Param::Integer(same.id),
+                        ],
+                    )?;
+                    conn.execute_sql(
+                        "DELETE FROM timer_run_tags WHERE tag_id = ?",
+                        &[Param::Integer(tag.id)],
+                    )?;
+                    conn.execute_sql("DELETE FROM tags WHERE id = ?", &[Param::Integer(tag.id)])?;
+                }
+                None => {
+                    conn.execute_sql(
+                        "UPDATE tags SET name = ? WHERE id = ?",
+                        &[Param::Text(&normalized), Param::Integer(tag.id)],
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    })
+    .expect("Error normalizing tags");
+}
+
+/// Save a run dated today by the system clock. The timer dates runs by the user's
+/// `UserSettings` instead.
 pub fn create_timer_run(
     conn: &mut SqliteConnection,
-    user: &str,
+    user_id: &i32,
     working_time_secs: &i32,
     breaking_time_secs: &i32,
-) {
-    use crate::core::schema::timer_runs;
-
+    note: Option<&str>,
+    task_id: Option<&i32>,
+) -> i32 {
     let local: NaiveDate = Local::now().date_naive();
 
     let new_run = NewTimerRun {
-        user,
+        user_id,
         working_time_secs,
         date: &local,
         breaking_time_secs,
+        note,
+        task_id,
+        uuid: &Uuid::new_v4().to_string(),
     };
 
-    diesel::insert_into(timer_runs::table)
-        .values(&new_run)
-        .execute(conn)
-        .expect("Error saving new timer run");
+    insert_timer_run(conn, &new_run)
 }
 
-pub fn get_timer_runs(conn: &mut SqliteConnection, username: &str) -> Vec<TimerRuns> {
-    use crate::core::schema::timer_runs::dsl::*;
+/// Save a timer run as given, returning its id
+pub fn insert_timer_run<C: DbConnection>(conn: &mut C, new_run: &NewTimerRun) -> i32
+where
+    RowId: QueryableByName<C::Backend>,
+{
+    conn.load_rows::<RowId>(
+        "INSERT INTO timer_runs \
+             (user_id, working_time_secs, breaking_time_secs, date, note, task_id, uuid) \
+         VALUES (?, ?, ?, ?, ?, ?, ?) \
+         RETURNING id",
+        &[
+            Param::Integer(*new_run.user_id),
+            Param::Integer(*new_run.working_time_secs),
+            Param::Integer(*new_run.breaking_time_secs),
+            Param::Date(*new_run.date),
+            Param::NullableText(new_run.note),
+            Param::NullableInteger(new_run.task_id.copied()),
+            Param::Text(new_run.uuid),
+        ],
+    )
+    .expect("Error saving new timer run")
+    .pop()
+    .expect("Error saving new timer run")
+    .id
+}
 
-    let results = timer_runs
-        .filter(user.eq(username))
-        .select(TimerRuns::as_select())
-        .load(conn)
-        .expect("Error loading timer runs");
+/// Set or replace the note of a timer run
+pub fn set_timer_run_note<C: DbConnection>(conn: &mut C, timer_run_id: &i32, text: &str) {
+    conn.execute_sql(
+        "UPDATE timer_runs SET note = ? WHERE id = ?",
+        &[Param::Text(text), Param::Integer(*timer_run_id)],
+    )
+    .expect("Error saving note");
+}
 
-    results
+/// Get the id of the user's most recently recorded timer run
+pub fn get_last_timer_run_id<C: DbConnection>(conn: &mut C, user: &i32) -> Option<i32>
+where
+    RowId: QueryableByName<C::Backend>,
+{
+    conn.load_rows::<RowId>(
+        "SELECT id FROM timer_runs WHERE user_id = ? ORDER BY id DESC LIMIT 1",
+        &[Param::Integer(*user)],
+    )
+    .expect("Error loading timer runs")
+    .pop()
+    .map(|row| row.id)
 }
 
-pub fn get_users(conn: &mut SqliteConnection) -> Vec<String> {
+/// The uuids of all timer runs of all users
+pub fn get_timer_run_uuids<C: DbConnection>(conn: &mut C) -> HashSet<String>
+where
+    RowText: QueryableByName<C::Backend>,
+{
+    conn.load_rows::<RowText>("SELECT uuid AS value FROM timer_runs", &[])
+        .expect("Error loading timer runs")
+        .into_iter()
+        .map(|row| row.value)
+        .collect()
+}
+
+/// Attach the given tags to a timer run, normalized, creating tags that do not exist yet
+pub fn add_tags_to_timer_run<C: DbConnection>(
+    conn: &mut C,
+    timer_run_id: &i32,
+    tag_names: &[String],
+) where
+    RowId: QueryableByName<C::Backend>,
+{
+    for tag_name in &normalize_tags(tag_names) {
+        conn.execute_sql(
+            "INSERT INTO tags (name) VALUES (?) ON CONFLICT DO NOTHING",
+            &[Param::Text(tag_name)],
+        )
+        .expect("Error saving tag");
+
+        let tag_id = conn
+            .load_rows::<RowId>(
+                "SELECT id FROM tags WHERE name = ?",
+                &[Param::Text(tag_name)],
+            )
+            .expect("Error loading tag")
+            .pop()
+            .expect("Error loading tag")
+            .id;
+
+        conn.execute_sql(
+            "INSERT INTO timer_run_tags (timer_run_id, tag_id) VALUES (?, ?) \
+             ON CONFLICT DO NOTHING",
+            &[Param::Integer(*timer_run_id), Param::Integer(tag_id)],
+        )
+        .expect("Error tagging timer run");
+    }
+}
+
+pub fn get_timer_runs(conn: &mut SqliteConnection, user: &i32) -> Vec<TimerRuns> {
     use crate::core::schema::timer_runs::dsl::*;
+
     timer_runs
-        .select(user)
-        .distinct()
+        .filter(user_id.eq(user))
+        .select(TimerRuns::as_select())
         .load(conn)
         .expect("Error loading timer runs")
 }
+
+/// Get the user's timer runs paired with each of their tag names. Untagged runs are left out.
+pub fn get_tagged_timer_runs(
+    conn: &mut SqliteConnection,
+    user_id: &i32,
+) -> Vec<(TimerRuns, String)> {
+    use crate::core::schema::{tags, timer_run_tags, timer_runs};
+
+    timer_runs::table
+        .inner_join(timer_run_tags::table.inner_join(tags::table))
+        .filter(timer_runs::user_id.eq(user_id))
+        .select((TimerRuns::as_select(), tags::name))
+        .load(conn)
+        .expect("Error loading tagged timer runs")
+}
+
+pub fn get_timer_run<C: DbConnection>(conn: &mut C, timer_run_id: &i32) -> Option<TimerRuns>
+where
+    TimerRuns: QueryableByName<C::Backend>,
+{
+    conn.load_rows(
+        "SELECT * FROM timer_runs WHERE id = ?",
+        &[Param::Integer(*timer_run_id)],
+    )
+    .expect("Error loading timer run")
+    .pop()
+}
+
+/// Get the names of a timer run's tags, sorted
+pub fn get_timer_run_tags<C: DbConnection>(conn: &mut C, timer_run_id: &i32) -> Vec<String>
+where
+    RowText: QueryableByName<C::Backend>,
+{
+    conn.load_rows::<RowText>(
+        "SELECT tags.name AS value \
+         FROM timer_run_tags \
+         JOIN tags ON tags.id = timer_run_tags.tag_id \
+         WHERE timer_run_tags.timer_run_id = ? \
+         ORDER BY tags.name",
+        &[Param::Integer(*timer_run_id)],
+    )
+    .expect("Error loading tags")
+    .into_iter()
+    .map(|row| row.value)
+    .collect()
+}
+
+/// Replace the timer run having the same id with the given one
+pub fn update_timer_run<C: DbConnection>(conn: &mut C, timer_run: &TimerRuns) {
+    conn.execute_sql(
+        "UPDATE timer_runs SET user_id = ?, working_time_secs = ?, breaking_time_secs = ?, \
+             date = ?, note = ?, task_id = ?, uuid = ? \
+         WHERE id = ?",
+        &[
+            Param::Integer(timer_run.user_id),
+            Param::Integer(timer_run.working_time_secs),
+            Param::Integer(timer_run.breaking_time_secs),
+            Param::Date(timer_run.date),
+            Param::NullableText(timer_run.note.as_deref()),
+            Param::NullableInteger(timer_run.task_id),
+            Param::Text(&timer_run.uuid),
+            Param::Integer(timer_run.id),
+        ],
+    )
+    .expect("Error saving timer run");
+}
+
+/// Delete a timer run and its tags, returning whether there was such a run
+pub fn delete_timer_run<C: DbConnection>(conn: &mut C, timer_run_id: &i32) -> bool {
+    // SQLite only cascades deletes with foreign keys turned on, so remove the tags explicitly
+    conn.execute_sql(
+        "DELETE FROM timer_run_tags WHERE timer_run_id = ?",
+        &[Param::Integer(*timer_run_id)],
+    )
+    .expect("Error deleting tags of timer run");
+
+    conn.execute_sql(
+        "DELETE FROM timer_runs WHERE id = ?",
+        &[Param::Integer(*timer_run_id)],
+    )
+    .expect("Error deleting timer run")
+        > 0
+}
+
+/// Save a deleted timer run again as it was, keeping its id
+pub fn reinsert_timer_run<C: DbConnection>(
+    conn: &mut C,
+    timer_run: &TimerRuns,
+    tag_names: &[String],
+) where
+    RowId: QueryableByName<C::Backend>,
+{
+    conn.execute_sql(
+        "INSERT INTO timer_runs \
+             (id, user_id, working_time_secs, breaking_time_secs, date, note, task_id, uuid) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        &[
+            Param::Integer(timer_run.id),
+            Param::Integer(timer_run.user_id),
+            Param::Integer(timer_run.working_time_secs),
+            Param::Integer(timer_run.breaking_time_secs),
+            Param::Date(timer_run.date),
+            Param::NullableText(timer_run.note.as_deref()),
+            Param::NullableInteger(timer_run.task_id),
+            Param::Text(&timer_run.uuid),
+        ],
+    )
+    .expect("Error saving timer run");
+
+    add_tags_to_timer_run(conn, &timer_run.id, tag_names);
+}
+
+pub fn get_users<C: DbConnection>(conn: &mut C) -> Vec<String>
+where
+    RowText: QueryableByName<C::Backend>,
+{
+    conn.load_rows::<RowText>("SELECT name AS value FROM users ORDER BY name", &[])
+        .expect("Error loading users")
+        .into_iter()
+        .map(|row| row.value)
+        .collect()
+}
+
+pub fn get_user<C: DbConnection>(conn: &mut C, username: &str) -> Option<User>
+where
+    User: QueryableByName<C::Backend>,
+{
+    conn.load_rows(
+        "SELECT * FROM users WHERE name = ?",
+        &[Param::Text(username)],
+    )
+    .expect("Error loading user")
+    .pop()
+}
+
+/// Look up the user with the given name, creating it if it does not exist
+pub fn get_or_create_user<C: DbConnection>(conn: &mut C, username: &str) -> User
+where
+    User: QueryableByName<C::Backend>,
+{
+    conn.execute_sql(
+        "INSERT INTO users (name) VALUES (?) ON CONFLICT DO NOTHING",
+        &[Param::Text(username)],
+    )
+    .expect("Error saving user");
+
+    get_user(conn, username).expect("Error loading user")
+}
+
+/// Save the user's settings, replacing the ones saved before
+pub fn save_user_settings<C: DbConnection>(
+    conn: &mut C,
+    user_id: &i32,
+    user_settings: &UserSettings,
+) {
+    let json = serde_json::to_string(user_settings).expect("Error writing settings");
+    conn.execute_sql(
+        "UPDATE users SET settings = ? WHERE id = ?",
+        &[Param::Text(&json), Param::Integer(*user_id)],
+    )
+    .expect("Error saving settings");
+}
+
+/// Set or remove the user's password hash, which also clears failed sign ins
+pub fn save_password_hash<C: DbConnection>(conn: &mut C, user_id: &i32, hash: Option<&str>) {
+    conn.execute_sql(
+        "UPDATE users SET password_hash = ?, failed_sign_ins = 0, locked_until = NULL \
+         WHERE id = ?",
+        &[Param::NullableText(hash), Param::Integer(*user_id)],
+    )
+    .expect("Error saving password");
+}
+
+/// Save the number of wrong passwords entered for the user, and until when they are locked out
+pub fn save_failed_sign_ins<C: DbConnection>(
+    conn: &mut C,
+    user_id: &i32,
+    failed: &i32,
+    until: Option<&NaiveDateTime>,
+) {
+    conn.execute_sql(
+        "UPDATE users SET failed_sign_ins = ?, locked_until = ? WHERE id = ?",
+        &[
+            Param::Integer(*failed),
+            Param::NullableTimestamp(until.copied()),
+            Param::Integer(*user_id),
+        ],
+    )
+    .expect("Error saving failed sign ins");
+}
+
+/// Rename a user, keeping their runs and tasks. Fails if there is no user with the old name
+/// or someone already has the new one.
+pub fn rename_user<C: DbConnection>(
+    conn: &mut C,
+    old_name: &str,
+    new_name: &str,
+) -> Result<(), String>
+where
+    RowId: QueryableByName<C::Backend>,
+{
+    let already_exists = || format!("{} already exists, merge the users instead", new_name);
+
+    let renamed = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let user = match get_user_id(conn, old_name) {
+            Ok(user) => user,
+            Err(error) => return Ok(Err(error)),
+        };
+        if get_user_id(conn, new_name).is_ok() {
+            return Ok(Err(already_exists()));
+        }
+
+        conn.execute_sql(
+            "UPDATE users SET name = ? WHERE id = ?",
+            &[Param::Text(new_name), Param::Integer(user)],
+        )?;
+        Ok(Ok(()))
+    });
+
+    // Someone else can take the name between the check and the update
+    match renamed {
+        Ok(result) => result,
+        Err(diesel::result::Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => {
+            Err(already_exists())
+        }
+        Err(error) => panic!("Error renaming user: {}", error),
+    }
+}
+
+/// Move all runs and tasks of one user to another and delete the first one. Tasks both users
+/// have are merged into the second user's task.
+pub fn merge_users<C: DbConnection>(
+    conn: &mut C,
+    from_name: &str,
+    into_name: &str,
+) -> Result<(), String>
+where
+    RowId: QueryableByName<C::Backend>,
+    Task: QueryableByName<C::Backend>,
+{
+    let from = get_user_id(conn, from_name)?;
+    let into = get_user_id(conn, into_name)?;
+    if from == into {
+        return Err(String::from("Cannot merge a user into themselves"));
+    }
+
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let from_tasks: Vec<Task> = conn.load_rows(
+            "SELECT * FROM tasks WHERE user_id = ?",
+            &[Param::Integer(from)],
+        )?;
+
+        for task in from_tasks {
+            let into_task: Option<RowId> = conn
+                .load_rows(
+                    "SELECT id FROM tasks WHERE user_id = ? AND name = ?",
+                    &[Param::Integer(into), Param::Text(&task.name)],
+                )?
+                .pop();
+
+            match into_task {
+                Some(into_task) => {
+                    conn.execute_sql(
+                        "UPDATE timer_runs SET task_id = ? WHERE task_id = ?",
+                        &[Param::Integer(into_task.id), Param::Integer(task.id)],
+                    )?;
+                    conn.execute_sql("DELETE FROM tasks WHERE id = ?", &[Param::Integer(task.id)])?;
+                }
+                None => {
+                    conn.execute_sql(
+                        "UPDATE tasks SET user_id = ? WHERE id = ?",
+                        &[Param::Integer(into), Param::Integer(task.id)],
+                    )?;
+                }
+            }
+        }
+
+        conn.execute_sql(
+            "UPDATE timer_runs SET user_id = ? WHERE user_id = ?",
+            &[Param::Integer(into), Param::Integer(from)],
+        )?;
+        conn.execute_sql("DELETE FROM users WHERE id = ?", &[Param::Integer(from)])?;
+        Ok(())
+    })
+    .expect("Error merging users");
+
+    Ok(())
+}
+
+/// Id of the user with the name, an error naming them if there is none
+fn get_user_id<C: DbConnection>(conn: &mut C, username: &str) -> Result<i32, String>
+where
+    RowId: QueryableByName<C::Backend>,
+{
+    conn.load_rows::<RowId>(
+        "SELECT id FROM users WHERE name = ?",
+        &[Param::Text(username)],
+    )
+    .expect("Error loading user")
+    .pop()
+    .map(|row| row.id)
+    .ok_or_else(|| format!("There is no user {}", username))
+}
+
+/// Create a task for the user, created on the given day, or update its estimate if the user
+/// already has a task with that name
+pub fn save_task<C: DbConnection>(
+    conn: &mut C,
+    user_id: &i32,
+    task_name: &str,
+    estimated_pomodoros: &i32,
+    created: &NaiveDate,
+) -> Task
+where
+    Task: QueryableByName<C::Backend>,
+{
+    conn.load_rows(
+        "INSERT INTO tasks (user_id, name, estimated_pomodoros, created) VALUES (?, ?, ?, ?) \
+         ON CONFLICT (user_id, name) DO UPDATE SET estimated_pomodoros = excluded.estimated_pomodoros \
+         RETURNING *",
+        &[
+            Param::Integer(*user_id),
+            Param::Text(task_name),
+            Param::Integer(*estimated_pomodoros),
+            Param::Date(*created),
+        ],
+    )
+    .expect("Error saving task")
+    .pop()
+    .expect("Error saving task")
+}
+
+pub fn get_task<C: DbConnection>(conn: &mut C, user: &i32, task_name: &str) -> Option<Task>
+where
+    Task: QueryableByName<C::Backend>,
+{
+    conn.load_rows(
+        "SELECT * FROM tasks WHERE user_id = ? AND name = ?",
+        &[Param::Integer(*user), Param::Text(task_name)],
+    )
+    .expect("Error loading task")
+    .pop()
+}
+
+/// Get the user's tasks together with the number of completed pomodoros for each
+pub fn get_tasks_with_actuals<C: DbConnection>(conn: &mut C, user_id: &i32) -> Vec<(Task, i64)>
+where
+    TaskRow: QueryableByName<C::Backend>,
+{
+    conn.load_rows::<TaskRow>(
+        "SELECT tasks.*, COUNT(timer_runs.id) AS actual \
+         FROM tasks \
+         LEFT JOIN timer_runs ON timer_runs.task_id = tasks.id \
+         WHERE tasks.user_id = ? \
+         GROUP BY tasks.id \
+         ORDER BY tasks.id",
+        &[Param::Integer(*user_id)],
+    )
+    .expect("Error loading tasks")
+    .into_iter()
+    .map(|row| (row.task, row.actual))
+    .collect()
+}
+
+/// Get the user's runs with their task and tags, optionally only those within the inclusive date range
+pub fn get_run_records<C: DbConnection>(
+    conn: &mut C,
+    user_id: &i32,
+    date_range: Option<(NaiveDate, NaiveDate)>,
+) -> Vec<RunRecord>
+where
+    RunRow: QueryableByName<C::Backend>,
+    RunTag: QueryableByName<C::Backend>,
+{
+    let mut params = vec![Param::Integer(*user_id)];
+    params.extend(date_range_params(date_range));
+
+    let runs: Vec<RunRow> = conn
+        .load_rows(
+            &format!(
+                "SELECT timer_runs.*, users.name AS user_name, tasks.name AS task_name \
+                 FROM timer_runs \
+                 JOIN users ON users.id = timer_runs.user_id \
+                 LEFT JOIN tasks ON tasks.id = timer_runs.task_id \
+                 WHERE timer_runs.user_id = ? {} \
+                 ORDER BY timer_runs.id",
+                date_range_condition(date_range)
+            ),
+            &params,
+        )
+        .expect("Error loading timer runs");
+
+    let mut run_tags: HashMap<i32, Vec<String>> = HashMap::new();
+    conn.load_rows::<RunTag>(
+        "SELECT timer_run_tags.timer_run_id, tags.name \
+         FROM timer_run_tags \
+         JOIN timer_runs ON timer_runs.id = timer_run_tags.timer_run_id \
+         JOIN tags ON tags.id = timer_run_tags.tag_id \
+         WHERE timer_runs.user_id = ? \
+         ORDER BY tags.name",
+        &[Param::Integer(*user_id)],
+    )
+    .expect("Error loading tags")
+    .into_iter()
+    .for_each(|tag| run_tags.entry(tag.timer_run_id).or_default().push(tag.name));
+
+    runs.into_iter()
+        .map(
+            |RunRow {
+                 run,
+                 user_name,
+                 task_name,
+             }| RunRecord {
+                id: run.id,
+                date: run.date,
+                user: user_name,
+                working_time_secs: run.working_time_secs,
+                breaking_time_secs: run.breaking_time_secs,
+                tags: run_tags.remove(&run.id).unwrap_or_default(),
+                task: task_name,
+                note: run.note,
+                uuid: run.uuid,
+            },
+        )
+        .collect()
+}
+
+/// Write run records as CSV with the `CSV_COLUMNS` header, or as a JSON array
+pub fn write_run_records<W: Write>(
+    records: &[RunRecord],
+    format: ExportFormat,
+    writer: W,
+) -> io::Result<()> {
+    match format {
+        ExportFormat::Csv => {
+            let mut csv_writer = csv::Writer::from_writer(writer);
+            csv_writer.write_record(CSV_COLUMNS)?;
+            for record in records {
+                csv_writer.write_record([
+                    record.id.to_string(),
+                    record.date.to_string(),
+                    record.user.clone(),
+                    record.working_time_secs.to_string(),
+                    record.breaking_time_secs.to_string(),
+                    record.tags.join(";"),
+                    record.task.clone().unwrap_or_default(),
+                    record.note.clone().unwrap_or_default(),
+                    record.uuid.clone(),
+                ])?;
+            }
+            csv_writer.flush()
+        }
+        ExportFormat::Json => {
+            let mut writer = writer;
+            serde_json::to_writer_pretty(&mut writer, records)?;
+            writer.flush()
+        }
+    }
+}
+
+/// Sum up the user's runs, optionally only those within the inclusive date range or with a tag
+pub fn sum_timer_runs<C: DbConnection>(
+    conn: &mut C,
+    user: &i32,
+    date_range: Option<(NaiveDate, NaiveDate)>,
+    tag: Option<&str>,
+) -> RunTotals
+where
+    RunTotals: QueryableByName<C::Backend>,
+{
+    let tag = tag.map(normalize_tag);
+    let mut params = vec![Param::Integer(*user)];
+    params.extend(date_range_params(date_range));
+    let tag_condition = match &tag {
+        Some(tag) => {
+            params.push(Param::Text(tag));
+            "AND EXISTS (SELECT 1 FROM timer_run_tags \
+                 JOIN tags ON tags.id = timer_run_tags.tag_id \
+                 WHERE timer_run_tags.timer_run_id = timer_runs.id AND tags.name = ?)"
+        }
+        None => "",
+    };
+
+    conn.load_rows(
+        &format!(
+            "SELECT COALESCE(SUM(working_time_secs), 0) AS working_time_secs, \
+                 COALESCE(SUM(breaking_time_secs), 0) AS breaking_time_secs, \
+                 COUNT(*) AS runs \
+             FROM timer_runs \
+             WHERE timer_runs.user_id = ? {} {}",
+            date_range_condition(date_range),
+            tag_condition
+        ),
+        &params,
+    )
+    .expect("Error summing timer runs")
+    .pop()
+    .unwrap_or_default()
+}
+
+/// Sum up the user's runs per tag, optionally only those within the inclusive date range.
+/// Sorted by tag name, untagged runs are left out.
+pub fn get_tag_run_totals<C: DbConnection>(
+    conn: &mut C,
+    user: &i32,
+    date_range: Option<(NaiveDate, NaiveDate)>,
+) -> Vec<GroupedRunTotals>
+where
+    GroupedRunTotals: QueryableByName<C::Backend>,
+{
+    load_run_totals(
+        conn,
+        "tags.name",
+        "JOIN timer_run_tags ON timer_run_tags.timer_run_id = timer_runs.id \
+         JOIN tags ON tags.id = timer_run_tags.tag_id",
+        user,
+        date_range,
+    )
+}
+
+/// Sum up the user's runs per task, optionally only those within the inclusive date range.
+/// Sorted by task name, runs without a task are left out.
+pub fn get_task_run_totals<C: DbConnection>(
+    conn: &mut C,
+    user: &i32,
+    date_range: Option<(NaiveDate, NaiveDate)>,
+) -> Vec<GroupedRunTotals>
+where
+    GroupedRunTotals: QueryableByName<C::Backend>,
+{
+    load_run_totals(
+        conn,
+        "tasks.name",
+        "JOIN tasks ON tasks.id = timer_runs.task_id",
+        user,
+        date_range,
+    )
+}
+
+/// Sum up the user's runs per day, week or month, optionally only those within the inclusive
+/// date range. Sorted by date, days without runs are left out.
+pub fn get_grouped_run_totals<C: DbConnection>(
+    conn: &mut C,
+    user: &i32,
+    date_range: Option<(NaiveDate, NaiveDate)>,
+    grouping: Grouping,
+) -> Vec<GroupedRunTotals>
+where
+    GroupedRunTotals: QueryableByName<C::Backend>,
+{
+    let key = C::Backend::group_key(grouping);
+    load_run_totals(conn, &key, "", user, date_range)
+}
+
+/// Sum up the user's runs per `key`, an SQL expression over `timer_runs` and the tables
+/// of the `joins`. Sorted by key.
+fn load_run_totals<C: DbConnection>(
+    conn: &mut C,
+    key: &str,
+    joins: &str,
+    user: &i32,
+    date_range: Option<(NaiveDate, NaiveDate)>,
+) -> Vec<GroupedRunTotals>
+where
+    GroupedRunTotals: QueryableByName<C::Backend>,
+{
+    let mut params = vec![Param::Integer(*user)];
+    params.extend(date_range_params(date_range));
+
+    conn.load_rows(
+        &format!(
+            "SELECT {key} AS key, \
+                 SUM(timer_runs.working_time_secs) AS working_time_secs, \
+                 SUM(timer_runs.breaking_time_secs) AS breaking_time_secs, \
+                 COUNT(*) AS runs \
+             FROM timer_runs {joins} \
+             WHERE timer_runs.user_id = ? {} \
+             GROUP BY {key} \
+             ORDER BY {key}",
+            date_range_condition(date_range)
+        ),
+        &params,
+    )
+    .expect("Error summing timer runs")
+}
+
+/// SQL condition limiting `timer_runs.date` to the range, if there is one
+fn date_range_condition(date_range: Option<(NaiveDate, NaiveDate)>) -> &'static str {
+    match date_range {
+        Some(_) => "AND timer_runs.date BETWEEN ? AND ?",
+        None => "",
+    }
+}
+
+/// Parameters for the placeholders of `date_range_condition`. Bounds outside of the years
+/// 1 to 9999, like `NaiveDate::MAX` for a range without an end, are moved into them: SQLite
+/// compares dates as text, where `+262142-12-31` sorts before `2026-01-01`.
+fn date_range_params<'a>(date_range: Option<(NaiveDate, NaiveDate)>) -> Vec<Param<'a>> {
+    let storable = |date: NaiveDate| {
+        date.clamp(
+            NaiveDate::from_ymd_opt(1, 1, 1).unwrap_or(NaiveDate::MIN),
+            NaiveDate::from_ymd_opt(9999, 12, 31).unwrap_or(NaiveDate::MAX),
+        )
+    };
+    match date_range {
+        Some((from, to)) => vec![Param::Date(storable(from)), Param::Date(storable(to))],
+        None => Vec::new(),
+    }
+}
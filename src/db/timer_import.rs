@@ -0,0 +1,320 @@
+use crate::core::models::{NewTimerRun, RunRecord};
+use crate::db::backup::prepare_backup;
+use crate::db::sqlite_storage::SqliteStorage;
+use crate::db::storage::Storage;
+use crate::db::timer_database::ExportFormat;
+use chrono::NaiveDate;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+use uuid::Uuid;
+
+/// Columns telling a Toggl Track "Detailed report" CSV export apart
+const TOGGL_COLUMNS: [&str; 3] = ["Start date", "Duration", "Description"];
+
+/// Outcome of an import
+#[derive(Debug, Default, PartialEq)]
+pub struct ImportReport {
+    /// Number of runs imported, or that would be imported in a dry run
+    pub imported: usize,
+    /// Number of rows skipped because the run already exists
+    pub duplicates: usize,
+    /// Row number (counting from 1, without the CSV header) and reason for each unreadable row
+    pub errors: Vec<(usize, String)>,
+}
+
+/// Read run records from our own CSV or JSON export, or from a Toggl Track CSV export.
+/// Each row is read on its own, so one bad row does not stop the others.
+pub fn parse_run_records<R: Read>(
+    reader: R,
+    format: ExportFormat,
+) -> io::Result<Vec<Result<RunRecord, String>>> {
+    match format {
+        ExportFormat::Json => {
+            let rows: Vec<serde_json::Value> = serde_json::from_reader(reader)?;
+            Ok(rows.into_iter().map(parse_own_json).collect())
+        }
+        ExportFormat::Csv => {
+            let mut csv_reader = csv::Reader::from_reader(reader);
+            let headers = csv_reader.headers()?.clone();
+            let has_columns = |columns: &[&str]| {
+                columns
+                    .iter()
+                    .all(|column| headers.iter().any(|header| header == *column))
+            };
+
+            let parse_row: fn(&HashMap<&str, &str>) -> Result<RunRecord, String> =
+                if has_columns(&["date", "working_time_secs", "breaking_time_secs"]) {
+                    parse_own_row
+                } else if has_columns(&TOGGL_COLUMNS) {
+                    parse_toggl_row
+                } else {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "Unknown CSV layout",
+                    ));
+                };
+
+            Ok(csv_reader
+                .records()
+                .map(|row| {
+                    let row = row.map_err(|e| e.to_string())?;
+                    let fields: HashMap<&str, &str> = headers.iter().zip(row.iter()).collect();
+                    parse_row(&fields)
+                })
+                .collect())
+        }
+    }
+}
+
+/// Import the runs in a file for the user. Runs that already exist are skipped, and with
+/// `dry_run` nothing is saved, but the report tells what would have happened.
+pub fn import_runs(
+    storage: &mut dyn Storage,
+    user_id: &i32,
+    path: &Path,
+    format: ExportFormat,
+    dry_run: bool,
+) -> io::Result<ImportReport> {
+    let rows = parse_run_records(BufReader::new(File::open(path)?), format)?;
+    let mut report = ImportReport::default();
+
+    let mut known_runs: HashSet<_> = storage
+        .get_run_records(user_id, None)
+        .iter()
+        .map(duplicate_key)
+        .collect();
+    let mut known_uuids = storage.get_timer_run_uuids();
+
+    for (index, row) in rows.into_iter().enumerate() {
+        let record = match row {
+            Ok(record) => record,
+            Err(e) => {
+                report.errors.push((index + 1, e));
+                continue;
+            }
+        };
+
+        if !known_runs.insert(duplicate_key(&record)) {
+            report.duplicates += 1;
+            continue;
+        }
+
+        // Keep the uuid of the export, unless it is missing or already taken
+        let uuid = match Uuid::parse_str(&record.uuid) {
+            Ok(uuid) if !known_uuids.contains(&uuid.to_string()) => uuid.to_string(),
+            _ => Uuid::new_v4().to_string(),
+        };
+        known_uuids.insert(uuid.clone());
+
+        if !dry_run {
+            insert_run_record(storage, user_id, &record, &uuid);
+        }
+        report.imported += 1;
+    }
+
+    Ok(report)
+}
+
+/// Import the runs of all users from another database file of this app, such as the one of
+/// another machine. Runs are told apart by their uuid, so merging the same file again, or a
+/// file that already has runs merged from this one, adds nothing twice. Users, tasks and tags
+/// are matched by name. The other file is read from a copy and left as it is.
+pub fn merge_database(storage: &mut dyn Storage, path: &Path) -> io::Result<ImportReport> {
+    if !path.is_file() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} does not exist", path.display()),
+        ));
+    }
+
+    let copy = env::temp_dir().join(format!("pomodorotimer-merge-{}.db", Uuid::new_v4()));
+    fs::copy(path, &copy)?;
+    let report =
+        prepare_backup(&copy).map(|_| merge_runs(storage, &mut SqliteStorage::open(&copy)));
+    fs::remove_file(&copy)?;
+
+    report
+}
+
+fn merge_runs(storage: &mut dyn Storage, other: &mut dyn Storage) -> ImportReport {
+    let mut report = ImportReport::default();
+    let mut known_runs = storage.get_timer_run_uuids();
+
+    for username in other.get_users() {
+        let other_user = other.get_or_create_user(&username);
+        let records = other.get_run_records(&other_user.id, None);
+        if records.is_empty() {
+            continue;
+        }
+
+        let user = storage.get_or_create_user(&username);
+        for record in records {
+            if !known_runs.insert(record.uuid.clone()) {
+                report.duplicates += 1;
+                continue;
+            }
+
+            insert_run_record(storage, &user.id, &record, &record.uuid);
+            report.imported += 1;
+        }
+    }
+
+    report
+}
+
+/// Runs count as the same when they have the same date, durations and note
+fn duplicate_key(record: &RunRecord) -> (NaiveDate, i32, i32, Option<String>) {
+    (
+        record.date,
+        record.working_time_secs,
+        record.breaking_time_secs,
+        record.note.clone(),
+    )
+}
+
+fn insert_run_record(storage: &mut dyn Storage, user_id: &i32, record: &RunRecord, uuid: &str) {
+    let task_id =
+        record
+            .task
+            .as_ref()
+            .map(|task_name| match storage.get_task(user_id, task_name) {
+                Some(task) => task.id,
+                None => storage.save_task(user_id, task_name, &1, &record.date).id,
+            });
+
+    storage.insert_timer_run(
+        &NewTimerRun {
+            user_id,
+            working_time_secs: &record.working_time_secs,
+            breaking_time_secs: &record.breaking_time_secs,
+            date: &record.date,
+            note: record.note.as_deref(),
+            task_id: task_id.as_ref(),
+            uuid,
+        },
+        &record.tags,
+    );
+}
+
+/// A row of our own CSV export, see `CSV_COLUMNS`
+fn parse_own_row(fields: &HashMap<&str, &str>) -> Result<RunRecord, String> {
+    Ok(RunRecord {
+        id: 0,
+        date: parse_date(required_field(fields, "date")?)?,
+        user: optional_field(fields, "user").unwrap_or_default(),
+        working_time_secs: parse_secs(required_field(fields, "working_time_secs")?)?,
+        breaking_time_secs: parse_secs(required_field(fields, "breaking_time_secs")?)?,
+        tags: split_list(fields.get("tags").copied().unwrap_or_default(), ';'),
+        task: optional_field(fields, "task"),
+        note: optional_field(fields, "note"),
+        uuid: optional_field(fields, "uuid").unwrap_or_default(),
+    })
+}
+
+/// A record of our own JSON export, checked like `parse_own_row` checks the CSV rows
+fn parse_own_json(row: serde_json::Value) -> Result<RunRecord, String> {
+    let record: RunRecord = serde_json::from_value(row).map_err(|e| e.to_string())?;
+    let non_empty = |text: Option<String>| {
+        text.map(|text| text.trim().to_string())
+            .filter(|text| !text.is_empty())
+    };
+
+    Ok(RunRecord {
+        id: 0,
+        user: record.user.trim().to_string(),
+        working_time_secs: check_secs(record.working_time_secs)?,
+        breaking_time_secs: check_secs(record.breaking_time_secs)?,
+        tags: record
+            .tags
+            .iter()
+            .map(|tag| tag.trim())
+            .filter(|tag| !tag.is_empty())
+            .map(String::from)
+            .collect(),
+        task: non_empty(record.task),
+        note: non_empty(record.note),
+        uuid: record.uuid.trim().to_string(),
+        ..record
+    })
+}
+
+/// A row of a Toggl Track "Detailed report" CSV export. Time entries have no breaks,
+/// and the project is kept as a tag.
+fn parse_toggl_row(fields: &HashMap<&str, &str>) -> Result<RunRecord, String> {
+    let mut tags = split_list(fields.get("Tags").copied().unwrap_or_default(), ',');
+    if let Some(project) = optional_field(fields, "Project") {
+        tags.push(project);
+    }
+
+    Ok(RunRecord {
+        id: 0,
+        date: parse_date(required_field(fields, "Start date")?)?,
+        user: optional_field(fields, "User").unwrap_or_default(),
+        working_time_secs: parse_hms(required_field(fields, "Duration")?)?,
+        breaking_time_secs: 0,
+        tags,
+        task: optional_field(fields, "Task"),
+        note: optional_field(fields, "Description"),
+        uuid: String::new(),
+    })
+}
+
+fn required_field<'a>(fields: &HashMap<&str, &'a str>, name: &str) -> Result<&'a str, String> {
+    match fields.get(name) {
+        Some(value) if !value.trim().is_empty() => Ok(value.trim()),
+        _ => Err(format!("Missing {}", name)),
+    }
+}
+
+fn optional_field(fields: &HashMap<&str, &str>, name: &str) -> Option<String> {
+    fields
+        .get(name)
+        .map(|value| value.trim())
+        .filter(|value| !value.is_empty())
+        .map(String::from)
+}
+
+fn split_list(list: &str, separator: char) -> Vec<String> {
+    list.split(separator)
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| format!("Invalid date {:?}", date))
+}
+
+fn parse_secs(secs: &str) -> Result<i32, String> {
+    secs.parse::<i32>()
+        .map_err(|_| format!("Invalid duration {:?}", secs))
+        .and_then(check_secs)
+}
+
+fn check_secs(secs: i32) -> Result<i32, String> {
+    match secs {
+        0.. => Ok(secs),
+        _ => Err(format!("Invalid duration {:?}", secs.to_string())),
+    }
+}
+
+/// Parse a `HH:MM:SS` duration into seconds
+fn parse_hms(duration: &str) -> Result<i32, String> {
+    let parts: Vec<Result<i32, _>> = duration.split(':').map(str::parse::<i32>).collect();
+
+    match parts[..] {
+        [Ok(hours), Ok(minutes), Ok(seconds)]
+            if hours >= 0 && (0..60).contains(&minutes) && (0..60).contains(&seconds) =>
+        {
+            hours
+                .checked_mul(3600)
+                .and_then(|secs| secs.checked_add(minutes * 60 + seconds))
+                .ok_or_else(|| format!("Invalid duration {:?}", duration))
+        }
+        _ => Err(format!("Invalid duration {:?}", duration)),
+    }
+}
@@ -1,13 +1,225 @@
+use chrono::{Datelike, Local};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+use pomodorotimer::app::heatmap::Heatmap;
+use pomodorotimer::app::keymap::Keymap;
 use pomodorotimer::app::tui_app::App;
-use pomodorotimer::core::pomodoro_timer::PomodoroTimer;
+use pomodorotimer::core::pomodoro_timer::{PomodoroTimer, SignInError};
+use pomodorotimer::db::backup::rotate_daily_backups;
+use pomodorotimer::db::sqlite_storage::SqliteStorage;
+use pomodorotimer::db::timer_database::{is_postgres_url, resolve_database_path};
+use std::env;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process;
+
+const USAGE: &str =
+    "Usage: pomodorotimer [--database <path or postgres:// URL>] [--keep-backups <days>] \
+     [--keymap <path>] [heatmap <user> [year]]";
+
+/// Days of daily backups kept when `--keep-backups` is not given
+const DEFAULT_KEEP_BACKUPS_DAYS: u32 = 7;
+
+const WORK_DURATION_SECS: u64 = 20 * 60;
+const BREAK_DURATION_SECS: u64 = 5 * 60;
 
 /// Personal pomodoro timer main function
 fn main() {
-    // Create the timer
-    let timer = PomodoroTimer::new(20 * 60, 5 * 60);
+    let options = parse_options();
+    let database_path = resolve_database_path(options.database);
+
+    // Create the timer with a connection that is kept for the whole session
+    let timer = if is_postgres_url(&database_path) {
+        postgres_timer(&database_path.to_string_lossy())
+    } else {
+        let mut storage = SqliteStorage::open(&database_path);
+        // Only checked at start up, a session left open past midnight backs up on the next start
+        if options.keep_backups_days > 0 {
+            let today = Local::now().date_naive();
+            if let Err(e) = rotate_daily_backups(
+                &mut storage,
+                &database_path,
+                today,
+                options.keep_backups_days,
+            ) {
+                eprintln!("Could not make the daily backup: {}", e);
+            }
+        }
+        PomodoroTimer::new(WORK_DURATION_SECS, BREAK_DURATION_SECS, storage)
+    };
+
+    match options.command {
+        Some(Command::Heatmap { user, year }) => print_heatmap(timer, &user, year),
+        None => {
+            let keymap = match options.keymap.or_else(Keymap::default_path) {
+                Some(path) => Keymap::load(&path),
+                None => Ok(Keymap::default()),
+            }
+            .unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                process::exit(2);
+            });
+
+            // Run the TUI
+            let terminal = ratatui::init();
+            App::new(timer, keymap).run(terminal);
+            ratatui::restore();
+        }
+    }
+}
+
+/// Print the user's heatmap of the year, the current one when not given
+fn print_heatmap(mut timer: PomodoroTimer, user: &str, year: Option<i32>) {
+    if !timer.get_users().iter().any(|name| name == user) {
+        eprintln!("There is no user named {}", user);
+        process::exit(1);
+    }
+    let signed_in = match timer.sign_in(user, None) {
+        Err(SignInError::PasswordRequired) => {
+            let password = read_password(&format!("Password for {}: ", user));
+            timer.sign_in(user, Some(&password))
+        }
+        signed_in => signed_in,
+    };
+    if let Err(e) = signed_in {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+
+    let year = year.unwrap_or_else(|| timer.get_user_settings().today().year());
+    let days = timer.get_daily_work_time(year);
+    let heatmap = Heatmap::new(year, timer.get_week_start(), &days);
+    println!(
+        "{} worked {:.1} hours in {}",
+        user,
+        heatmap.total_secs() as f64 / 3600.0,
+        year
+    );
+    for line in heatmap.lines() {
+        println!("{}", line);
+    }
+}
+
+/// Read a password from the terminal without echoing it
+fn read_password(prompt: &str) -> String {
+    eprint!("{}", prompt);
+    io::stderr().flush().ok();
+    enable_raw_mode().expect("Could not read the password");
+
+    let mut password = String::new();
+    while let Ok(event) = event::read() {
+        let Event::Key(key) = event else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                disable_raw_mode().ok();
+                eprintln!();
+                process::exit(130);
+            }
+            KeyCode::Char(c) => password.push(c),
+            KeyCode::Backspace => {
+                password.pop();
+            }
+            KeyCode::Enter => break,
+            _ => {}
+        }
+    }
+
+    disable_raw_mode().ok();
+    eprintln!();
+    password
+}
+
+#[cfg(feature = "postgres")]
+fn postgres_timer(database_url: &str) -> PomodoroTimer {
+    use pomodorotimer::db::pg_storage::{establish_pg_connection, PgStorage};
+
+    let storage = PgStorage::new(establish_pg_connection(database_url));
+    PomodoroTimer::new(WORK_DURATION_SECS, BREAK_DURATION_SECS, storage)
+}
+
+#[cfg(not(feature = "postgres"))]
+fn postgres_timer(_database_url: &str) -> PomodoroTimer {
+    eprintln!("PostgreSQL support is not built in, rebuild with `--features postgres`");
+    process::exit(2);
+}
+
+enum Command {
+    /// Print a user's heatmap instead of running the TUI
+    Heatmap { user: String, year: Option<i32> },
+}
+
+struct Options {
+    database: Option<String>,
+    /// Days of daily backups to keep, 0 turns them off
+    keep_backups_days: u32,
+    /// Keymap file, the default location when not given
+    keymap: Option<PathBuf>,
+    command: Option<Command>,
+}
+
+/// Read `--database <path>`, `--keep-backups <days>` and `--keymap <path>` from the command
+/// line, also accepted as `--database=<path>` and so on, and the `heatmap <user> [year]` command
+fn parse_options() -> Options {
+    let mut options = Options {
+        database: None,
+        keep_backups_days: DEFAULT_KEEP_BACKUPS_DAYS,
+        keymap: None,
+        command: None,
+    };
+    let mut args = env::args().skip(1).peekable();
+
+    while let Some(arg) = args.next() {
+        let (flag, value) = match arg.split_once('=') {
+            Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+            None => (arg, None),
+        };
+
+        match flag.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            "--database" => {
+                options.database = Some(
+                    value
+                        .or_else(|| args.next())
+                        .unwrap_or_else(|| exit_with_usage()),
+                )
+            }
+            "--keep-backups" => {
+                options.keep_backups_days = value
+                    .or_else(|| args.next())
+                    .and_then(|days| days.parse().ok())
+                    .unwrap_or_else(|| exit_with_usage())
+            }
+            "--keymap" => {
+                options.keymap = Some(PathBuf::from(
+                    value
+                        .or_else(|| args.next())
+                        .unwrap_or_else(|| exit_with_usage()),
+                ))
+            }
+            "heatmap" if options.command.is_none() => {
+                let user = args.next().unwrap_or_else(|| exit_with_usage());
+                // The year is optional, so a flag after the user is left for the next round
+                let year = args
+                    .next_if(|arg| !arg.starts_with('-'))
+                    .map(|year| year.parse().unwrap_or_else(|_| exit_with_usage()));
+                options.command = Some(Command::Heatmap { user, year });
+            }
+            _ => exit_with_usage(),
+        }
+    }
+
+    options
+}
 
-    // Run the TUI
-    let terminal = ratatui::init();
-    App::new(timer).run(terminal);
-    ratatui::restore();
+fn exit_with_usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
 }
@@ -0,0 +1,168 @@
+mod common;
+
+mod backup_tests {
+    use crate::common::insert_run_on;
+    use chrono::NaiveDate;
+    use diesel::{sql_query, Connection, RunQueryDsl, SqliteConnection};
+    use pomodorotimer::db::backup::{backup_dir, rotate_daily_backups};
+    use pomodorotimer::db::memory_storage::MemoryStorage;
+    use pomodorotimer::db::sqlite_storage::SqliteStorage;
+    use pomodorotimer::db::storage::Storage;
+    use std::io::ErrorKind;
+    use std::path::PathBuf;
+    use std::{env, fs};
+
+    /// An empty directory for the test's files
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!(
+            "pomodorotimer-backup-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn should_restore_backup_taken_while_in_use() {
+        // Given a backup taken while the database is open
+        let dir = test_dir("restore");
+        let database_path = dir.join("pomodoro.db");
+        let mut storage = SqliteStorage::open(&database_path);
+        let alice = storage.get_or_create_user("alice").id;
+        insert_run_on(&mut storage, &alice, "2026-10-01", 1500, &["backend"], None);
+        storage.backup(&dir.join("snapshot.db")).unwrap();
+
+        // When more runs are recorded and the backup is restored
+        insert_run_on(&mut storage, &alice, "2026-10-01", 1200, &["backend"], None);
+        storage.restore(&dir.join("snapshot.db")).unwrap();
+
+        // Then only the runs in the backup are left, and the replaced database is kept
+        let records = storage.get_run_records(&alice, None);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].tags, vec!["backend"]);
+        assert!(dir.join("pomodoro.db.before-restore").exists());
+        assert!(!dir.join("pomodoro.db.restoring").exists());
+
+        // And backups are not overwritten
+        let error = storage.backup(&dir.join("snapshot.db")).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::AlreadyExists);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn should_refuse_to_restore_unknown_or_newer_databases() {
+        // Given a database in use, a text file and a backup from a newer version
+        let dir = test_dir("refuse");
+        let mut storage = SqliteStorage::open(&dir.join("pomodoro.db"));
+        let alice = storage.get_or_create_user("alice").id;
+        insert_run_on(&mut storage, &alice, "2026-10-01", 1500, &["backend"], None);
+
+        fs::write(dir.join("notes.txt"), "not a database").unwrap();
+        storage.backup(&dir.join("newer.db")).unwrap();
+        let mut newer =
+            SqliteConnection::establish(&dir.join("newer.db").to_string_lossy()).unwrap();
+        sql_query("INSERT INTO __diesel_schema_migrations (version) VALUES ('29990101000000')")
+            .execute(&mut newer)
+            .unwrap();
+
+        // When I try to restore them
+        let results = [
+            storage.restore(&dir.join("notes.txt")),
+            storage.restore(&dir.join("newer.db")),
+            storage.restore(&dir.join("missing.db")),
+        ];
+
+        // Then nothing is restored and the database is left as it was
+        let kinds: Vec<ErrorKind> = results
+            .iter()
+            .map(|r| r.as_ref().unwrap_err().kind())
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ErrorKind::InvalidData,
+                ErrorKind::InvalidData,
+                ErrorKind::NotFound
+            ]
+        );
+        assert_eq!(storage.get_run_records(&alice, None).len(), 1);
+        assert!(!dir.join("pomodoro.db.before-restore").exists());
+        assert!(!dir.join("pomodoro.db.restoring").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn should_clean_up_when_the_replaced_database_cannot_be_kept() {
+        // Given a valid backup, and a directory where the replaced database would be kept
+        let dir = test_dir("cleanup");
+        let mut storage = SqliteStorage::open(&dir.join("pomodoro.db"));
+        let alice = storage.get_or_create_user("alice").id;
+        storage.backup(&dir.join("snapshot.db")).unwrap();
+        insert_run_on(&mut storage, &alice, "2026-10-01", 1500, &["backend"], None);
+        fs::create_dir_all(dir.join("pomodoro.db.before-restore").join("taken")).unwrap();
+
+        // When I try to restore the backup
+        let result = storage.restore(&dir.join("snapshot.db"));
+
+        // Then the copy of the backup is removed and the database is still in use
+        assert!(result.is_err());
+        assert!(!dir.join("pomodoro.db.restoring").exists());
+        insert_run_on(&mut storage, &alice, "2026-10-02", 1200, &[], None);
+        assert_eq!(storage.get_run_records(&alice, None).len(), 2);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn should_keep_daily_backups_for_configured_days() {
+        // Given backups from earlier days and a file that is not a daily backup
+        let dir = test_dir("rotate");
+        let database_path = dir.join("pomodoro.db");
+        let mut storage = SqliteStorage::open(&database_path);
+        let backups = backup_dir(&database_path);
+        fs::create_dir_all(&backups).unwrap();
+        for name in [
+            "pomodoro-2026-10-10.db",
+            "pomodoro-2026-10-12.db",
+            "pomodoro-manual.db",
+        ] {
+            fs::write(backups.join(name), "").unwrap();
+        }
+
+        // When the app starts twice on the same day, keeping 7 days
+        let today = NaiveDate::from_ymd_opt(2026, 10, 18).unwrap();
+        let first = rotate_daily_backups(&mut storage, &database_path, today, 7).unwrap();
+        let second = rotate_daily_backups(&mut storage, &database_path, today, 7).unwrap();
+
+        // Then today's backup is made once and only backups older than a week are deleted
+        assert_eq!(first, Some(backups.join("pomodoro-2026-10-18.db")));
+        assert_eq!(second, None);
+        let mut names: Vec<String> = fs::read_dir(&backups)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "pomodoro-2026-10-12.db",
+                "pomodoro-2026-10-18.db",
+                "pomodoro-manual.db"
+            ]
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn should_not_back_up_memory_storage() {
+        // Given storage without a database file
+        let mut storage = MemoryStorage::new();
+
+        // Then backups are not supported
+        let error = storage
+            .backup(&env::temp_dir().join("memory.db"))
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+    }
+}
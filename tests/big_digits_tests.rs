@@ -0,0 +1,57 @@
+mod big_digits_tests {
+    use pomodorotimer::app::big_digits::BigDigits;
+    use ratatui::buffer::Buffer;
+    use ratatui::layout::Rect;
+    use ratatui::widgets::Widget;
+
+    fn render(text: &str, width: u16, height: u16) -> Vec<String> {
+        let area = Rect::new(0, 0, width, height);
+        let mut buf = Buffer::empty(area);
+        BigDigits::new(text).render(area, &mut buf);
+
+        (0..height)
+            .map(|y| (0..width).map(|x| buf[(x, y)].symbol()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn should_draw_time_in_block_digits() {
+        // Given room for the smallest size only
+        // When I draw a time
+        let rows = render("10:07", 36, 5);
+
+        // Then it is drawn in blocks, centred
+        assert_eq!(
+            rows,
+            vec![
+                "     ██  ██████      ██████  ██████ ",
+                "     ██  ██  ██  ██  ██  ██      ██ ",
+                "     ██  ██  ██      ██  ██      ██ ",
+                "     ██  ██  ██  ██  ██  ██      ██ ",
+                "     ██  ██████      ██████      ██ ",
+            ]
+        );
+    }
+
+    #[test]
+    fn should_scale_to_the_available_space() {
+        // Given room for twice the size
+        let rows = render("00:00", 80, 12);
+
+        // Then every pixel is two rows high and four columns wide
+        assert_eq!(rows.iter().filter(|row| row.contains('█')).count(), 10);
+        assert_eq!(
+            rows[1].trim().chars().filter(|c| *c == '█').count(),
+            4 * 3 * 4
+        );
+    }
+
+    #[test]
+    fn should_fall_back_to_plain_text() {
+        // Given too little room for block digits
+        let rows = render("24:59", 9, 1);
+
+        // Then the time is written as it is
+        assert_eq!(rows, vec!["  24:59  "]);
+    }
+}
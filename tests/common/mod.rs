@@ -0,0 +1,67 @@
+//! Helpers shared by the integration tests. Every test file uses only some of them.
+#![allow(dead_code)]
+
+use chrono::NaiveDate;
+use pomodorotimer::core::models::NewTimerRun;
+use pomodorotimer::db::storage::Storage;
+use uuid::Uuid;
+
+/// The `YYYY-MM-DD` day
+pub fn day(date: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+}
+
+/// Owns what a `NewTimerRun` borrows, with a one minute break and a new uuid
+pub struct TestRun {
+    user_id: i32,
+    working_time_secs: i32,
+    breaking_time_secs: i32,
+    date: NaiveDate,
+    task_id: Option<i32>,
+    uuid: String,
+}
+
+impl TestRun {
+    /// A run of the user on a `YYYY-MM-DD` day
+    pub fn new(user_id: &i32, date: &str, working: i32) -> Self {
+        TestRun {
+            user_id: *user_id,
+            working_time_secs: working,
+            breaking_time_secs: 60,
+            date: day(date),
+            task_id: None,
+            uuid: Uuid::new_v4().to_string(),
+        }
+    }
+
+    pub fn task(mut self, task_id: Option<&i32>) -> Self {
+        self.task_id = task_id.copied();
+        self
+    }
+
+    pub fn new_timer_run(&self) -> NewTimerRun<'_> {
+        NewTimerRun {
+            user_id: &self.user_id,
+            working_time_secs: &self.working_time_secs,
+            breaking_time_secs: &self.breaking_time_secs,
+            date: &self.date,
+            note: None,
+            task_id: self.task_id.as_ref(),
+            uuid: &self.uuid,
+        }
+    }
+}
+
+/// Save a run of the user on a `YYYY-MM-DD` day with the tags and task, returning its id
+pub fn insert_run_on(
+    storage: &mut dyn Storage,
+    user_id: &i32,
+    date: &str,
+    working: i32,
+    tags: &[&str],
+    task_id: Option<&i32>,
+) -> i32 {
+    let tags: Vec<String> = tags.iter().map(|tag| tag.to_string()).collect();
+    let run = TestRun::new(user_id, date, working).task(task_id);
+    storage.insert_timer_run(&run.new_timer_run(), &tags)
+}
@@ -0,0 +1,52 @@
+mod heatmap_tests {
+    use chrono::{NaiveDate, Weekday};
+    use pomodorotimer::app::heatmap::Heatmap;
+    use std::collections::BTreeMap;
+
+    fn date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn should_shade_days_relative_to_the_best_one() {
+        // Given a best day, a day with a quarter of its time and runs in another year
+        let days = BTreeMap::from([
+            (date("2025-12-31"), 9000),
+            (date("2026-01-01"), 3600),
+            (date("2026-01-02"), 900),
+        ]);
+
+        // When I draw 2026, which starts on a Thursday, with weeks starting on Monday
+        let heatmap = Heatmap::new(2026, Weekday::Mon, &days);
+        let lines = heatmap.lines();
+
+        // Then the first week only has days from Thursday on, shaded by working time
+        assert_eq!(lines.len(), 9);
+        assert!(lines[0].starts_with("    Jan Feb"));
+        assert!(lines[1].starts_with("Mon  ·"));
+        assert!(lines[4].starts_with("    █·"));
+        assert!(lines[5].starts_with("Fri ░·"));
+        assert!(lines[6].starts_with("    ··"));
+        assert_eq!(lines[8], "    Less · ░ ▒ ▓ █ More");
+
+        // And the year ends in its 53rd week, only counting its own days
+        assert_eq!(lines[4].chars().count(), 4 + 53);
+        assert_eq!(heatmap.total_secs(), 4500);
+    }
+
+    #[test]
+    fn should_start_rows_on_the_week_start() {
+        // Given no runs at all
+        let days = BTreeMap::new();
+
+        // When I draw a year with weeks starting on Sunday
+        let lines = Heatmap::new(2026, Weekday::Sun, &days).lines();
+
+        // Then the rows start on Sunday and every day is unshaded
+        assert!(lines[1].starts_with("Sun "));
+        assert!(lines[3].starts_with("Tue "));
+        assert!(lines[1..8]
+            .iter()
+            .all(|line| line[4..].chars().all(|c| c == '·' || c == ' ')));
+    }
+}
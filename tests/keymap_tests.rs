@@ -0,0 +1,138 @@
+mod keymap_tests {
+    use pomodorotimer::app::keymap::{Action, Keymap};
+    use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use std::{env, fs};
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn should_keep_defaults_for_actions_left_out() {
+        // Given a keymap that only moves quit and help
+        let keymap = Keymap::from_toml(
+            r#"
+            quit = ["ctrl-c", "Q"]
+            help = ["f1"]
+            "#,
+        )
+        .unwrap();
+
+        // Then those actions use the new keys
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+            Some(Action::Quit)
+        );
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('Q'), KeyModifiers::SHIFT)),
+            Some(Action::Quit)
+        );
+        assert_eq!(
+            keymap.action(&press(KeyCode::F(1), KeyModifiers::NONE)),
+            Some(Action::Help)
+        );
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('q'), KeyModifiers::NONE)),
+            None
+        );
+        assert_eq!(keymap.keys(Action::Quit), "Ctrl-c/Q");
+
+        // And the others keep theirs
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char(' '), KeyModifiers::NONE)),
+            Some(Action::StartPause)
+        );
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('+'), KeyModifiers::SHIFT)),
+            Some(Action::Extend)
+        );
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('c'), KeyModifiers::NONE)),
+            None
+        );
+    }
+
+    #[test]
+    fn should_refuse_keys_bound_twice() {
+        // Given vim keys where `h` is taken from the heatmap but not rebound
+        let keymap = Keymap::from_toml(
+            r#"
+            previous-year = ["h"]
+            next-year = ["l"]
+            "#,
+        );
+
+        // Then loading names the key and both actions
+        assert_eq!(
+            keymap,
+            Err(String::from("h is bound to both heatmap and previous-year"))
+        );
+
+        // And moving the heatmap first makes it load
+        assert!(Keymap::from_toml(
+            r#"
+            heatmap = ["m"]
+            previous-year = ["h"]
+            next-year = ["l"]
+            "#,
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn should_refuse_unknown_actions_and_keys() {
+        assert_eq!(
+            Keymap::from_toml(r#"jump = ["j"]"#),
+            Err(String::from("Unknown action \"jump\""))
+        );
+        assert_eq!(
+            Keymap::from_toml(r#"quit = ["ctrl-"]"#),
+            Err(String::from("Unknown key \"ctrl-\""))
+        );
+        assert!(Keymap::from_toml(r#"quit = "q""#).is_err());
+    }
+
+    #[test]
+    fn should_refuse_to_unbind_quit_and_commands() {
+        assert_eq!(
+            Keymap::from_toml(r#"quit = []"#),
+            Err(String::from("quit needs at least one key"))
+        );
+        assert_eq!(
+            Keymap::from_toml(r#"enter-command = []"#),
+            Err(String::from("enter-command needs at least one key"))
+        );
+    }
+
+    #[test]
+    fn should_generate_help_from_the_active_keymap() {
+        // Given a keymap without a key for skipping
+        let keymap = Keymap::from_toml(r#"skip = []"#).unwrap();
+
+        // Then the help lists every action with its keys
+        let help = keymap.help();
+        assert_eq!(help.len(), Action::ALL.len());
+        assert!(help.contains(&(
+            String::from("Space"),
+            "Start a session, or pause and resume it"
+        )));
+        assert!(help.contains(&(String::from("none"), "Skip to the next phase")));
+        assert!(help.contains(&(String::from("Left"), "Heatmap of the year before")));
+    }
+
+    #[test]
+    fn should_use_defaults_without_a_keymap_file() {
+        // Given no keymap file
+        let dir = env::temp_dir().join(format!("pomodorotimer-keymap-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("keymap.toml");
+
+        // Then the defaults are used
+        assert_eq!(Keymap::load(&path), Ok(Keymap::default()));
+
+        // And a file is read once there is one
+        fs::write(&path, "quit = [\"x\"]").unwrap();
+        assert_eq!(Keymap::load(&path).unwrap().keys(Action::Quit), "x");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
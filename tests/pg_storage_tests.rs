@@ -0,0 +1,214 @@
+//! Run against a local PostgreSQL database with
+//! `POSTGRES_TEST_URL=postgres://localhost/pomodoro_test cargo test --features postgres`.
+//! Every test rolls back its changes.
+#![cfg(feature = "postgres")]
+
+mod common;
+
+mod pg_storage_tests {
+    use crate::common::{day, insert_run_on};
+    use chrono::{NaiveDate, Weekday};
+    use diesel::{Connection, SqliteConnection};
+    use diesel_migrations::MigrationHarness;
+    use pomodorotimer::db::pg_storage::{establish_pg_connection, PgStorage};
+    use pomodorotimer::db::sqlite_storage::SqliteStorage;
+    use pomodorotimer::db::storage::Storage;
+    use pomodorotimer::db::timer_database::{Grouping, MIGRATIONS};
+    use std::env;
+    use std::sync::{Mutex, MutexGuard};
+
+    /// Tests share the database, so they take turns to not wait on each other's rows
+    static DATABASE: Mutex<()> = Mutex::new(());
+
+    fn test_storage() -> (MutexGuard<'static, ()>, PgStorage) {
+        let guard = DATABASE.lock().unwrap_or_else(|e| e.into_inner());
+        let database_url = env::var("POSTGRES_TEST_URL")
+            .expect("Set POSTGRES_TEST_URL to a database the tests can use");
+
+        let mut conn = establish_pg_connection(&database_url);
+        conn.begin_test_transaction().unwrap();
+        (guard, PgStorage::new(conn))
+    }
+
+    #[test]
+    fn should_create_user_and_task_once() {
+        // Given a user with a task
+        let (_guard, mut storage) = test_storage();
+        let alice = storage.get_or_create_user("alice");
+        let task = storage.save_task(&alice.id, "report", &2, &day("2026-10-01"));
+
+        // When I look them up again and change the estimate
+        let again = storage.get_or_create_user("alice");
+        let updated = storage.save_task(&alice.id, "report", &4, &day("2026-10-01"));
+
+        // Then they are the same rows
+        assert_eq!(again.id, alice.id);
+        assert_eq!(updated.id, task.id);
+        assert_eq!(
+            storage
+                .get_task(&alice.id, "report")
+                .unwrap()
+                .estimated_pomodoros,
+            4
+        );
+
+        // And a password and lockout are saved on the user
+        let locked_until = NaiveDate::from_ymd_opt(2026, 10, 18)
+            .unwrap()
+            .and_hms_opt(12, 5, 0)
+            .unwrap();
+        storage.save_password_hash(&alice.id, Some("$argon2id$hash"));
+        storage.save_failed_sign_ins(&alice.id, &2, Some(&locked_until));
+        let locked = storage.get_or_create_user("alice");
+        assert_eq!(locked.password_hash.as_deref(), Some("$argon2id$hash"));
+        assert_eq!(
+            (locked.failed_sign_ins, locked.locked_until),
+            (2, Some(locked_until))
+        );
+    }
+
+    #[test]
+    fn should_record_tagged_runs_with_notes() {
+        // Given a run tagged twice with the same tag
+        let (_guard, mut storage) = test_storage();
+        let alice = storage.get_or_create_user("alice").id;
+        let run_id = insert_run_on(
+            &mut storage,
+            &alice,
+            "2026-10-01",
+            1500,
+            &["db", "db"],
+            None,
+        );
+
+        // When I attach a note
+        storage.set_timer_run_note(&run_id, "schema review");
+
+        // Then the run comes back with one tag and the note
+        assert_eq!(storage.get_last_timer_run_id(&alice), Some(run_id));
+        let records = storage.get_run_records(&alice, None);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].tags, vec!["db"]);
+        assert_eq!(records[0].note.as_deref(), Some("schema review"));
+    }
+
+    #[test]
+    fn should_delete_and_reinsert_runs_with_tags() {
+        // Given a tagged run
+        let (_guard, mut storage) = test_storage();
+        let alice = storage.get_or_create_user("alice").id;
+        let run_id = insert_run_on(&mut storage, &alice, "2026-10-01", 1500, &["db"], None);
+        let (run, tags) = storage.get_timer_run(&run_id).unwrap();
+
+        // When I delete it and put it back
+        assert!(storage.delete_timer_run(&run_id));
+        assert!(storage.get_timer_run(&run_id).is_none());
+        storage.reinsert_timer_run(&run, &tags);
+
+        // Then it has the same id and tags, and can still be edited
+        let mut edited = run.clone();
+        edited.note = Some(String::from("restored"));
+        storage.update_timer_run(&edited);
+        assert_eq!(storage.get_timer_run(&run_id), Some((edited, tags)));
+    }
+
+    #[test]
+    fn should_merge_users_with_their_tasks() {
+        // Given two spellings of a user, both with a report task
+        let (_guard, mut storage) = test_storage();
+        let typo = storage.get_or_create_user("Nikolaj").id;
+        let nikolaj = storage.get_or_create_user("nikolaj").id;
+        let typo_report = storage
+            .save_task(&typo, "report", &2, &day("2026-10-01"))
+            .id;
+        storage.save_task(&nikolaj, "report", &3, &day("2026-10-01"));
+        let run_id = insert_run_on(&mut storage, &typo, "2026-10-01", 1500, &[], None);
+        let (mut run, tags) = storage.get_timer_run(&run_id).unwrap();
+        run.task_id = Some(typo_report);
+        storage.update_timer_run(&run);
+
+        // When they are merged and renamed
+        assert!(storage.merge_users("Nikolaj", "nikolaj").is_ok());
+        assert!(storage.rename_user("nikolaj", "nik").is_ok());
+        assert!(storage.rename_user("nik", "nik").is_err());
+
+        // Then the run is on the remaining user's task
+        assert!(tags.is_empty());
+        let records = storage.get_run_records(&nikolaj, None);
+        assert_eq!(records[0].user, "nik");
+        assert_eq!(records[0].task.as_deref(), Some("report"));
+        assert_eq!(storage.get_tasks_with_actuals(&nikolaj).len(), 1);
+    }
+
+    #[test]
+    fn should_sum_and_group_like_sqlite() {
+        // Given the same runs, around a new year, in PostgreSQL and SQLite
+        let (_guard, mut postgres) = test_storage();
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.run_pending_migrations(MIGRATIONS).unwrap();
+        let mut sqlite = SqliteStorage::new(conn);
+
+        let mut users = vec![];
+        for storage in [&mut postgres as &mut dyn Storage, &mut sqlite] {
+            let alice = storage.get_or_create_user("alice").id;
+            insert_run_on(storage, &alice, "2026-12-28", 600, &["backend"], None);
+            let run = insert_run_on(
+                storage,
+                &alice,
+                "2027-01-01",
+                1500,
+                &["backend", "review"],
+                None,
+            );
+            let (mut timer_run, _) = storage.get_timer_run(&run).unwrap();
+            timer_run.task_id = Some(
+                storage
+                    .save_task(&alice, "report", &2, &day("2026-10-01"))
+                    .id,
+            );
+            storage.update_timer_run(&timer_run);
+            insert_run_on(storage, &alice, "2027-01-04", 1200, &[], None);
+            users.push(alice);
+        }
+
+        // Then every total and grouping matches
+        let january = Some((
+            NaiveDate::from_ymd_opt(2027, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2027, 1, 31).unwrap(),
+        ));
+        let (pg_alice, sqlite_alice) = (&users[0], &users[1]);
+        for (date_range, tag) in [(None, None), (january, None), (None, Some("review"))] {
+            assert_eq!(
+                postgres.sum_timer_runs(pg_alice, date_range, tag),
+                sqlite.sum_timer_runs(sqlite_alice, date_range, tag)
+            );
+        }
+
+        let keys = |groups: Vec<pomodorotimer::core::models::GroupedRunTotals>| {
+            groups
+                .into_iter()
+                .map(|group| (group.key, group.totals))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            keys(postgres.get_tag_run_totals(pg_alice, january)),
+            keys(sqlite.get_tag_run_totals(sqlite_alice, january))
+        );
+        assert_eq!(
+            keys(postgres.get_task_run_totals(pg_alice, january)),
+            keys(sqlite.get_task_run_totals(sqlite_alice, january))
+        );
+        for grouping in [
+            Grouping::Day,
+            Grouping::Week(Weekday::Mon),
+            Grouping::Week(Weekday::Sun),
+            Grouping::Week(Weekday::Sat),
+            Grouping::Month,
+        ] {
+            assert_eq!(
+                keys(postgres.get_grouped_run_totals(pg_alice, None, grouping)),
+                keys(sqlite.get_grouped_run_totals(sqlite_alice, None, grouping))
+            );
+        }
+    }
+}
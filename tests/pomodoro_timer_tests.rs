@@ -1,14 +1,33 @@
+mod common;
+
 mod pomodoro_timer_tests {
-    use pomodorotimer::core::pomodoro_timer::PomodoroTimer;
+    use crate::common::{day, insert_run_on};
+    use chrono::{DateTime, Days, NaiveDate, Weekday};
+    use diesel::{Connection, SqliteConnection};
+    use diesel_migrations::MigrationHarness;
+    use pomodorotimer::core::credentials::MAX_FAILED_SIGN_INS;
+    use pomodorotimer::core::models::UserSettings;
+    use pomodorotimer::core::pomodoro_timer::Period::Today;
     use pomodorotimer::core::pomodoro_timer::TimerState::{Breaking, Idle, Working};
+    use pomodorotimer::core::pomodoro_timer::{
+        Period, PomodoroTimer, RunEdit, SignInError, DASHBOARD_DAYS, DASHBOARD_WEEKS,
+    };
+    use pomodorotimer::db::memory_storage::MemoryStorage;
+    use pomodorotimer::db::sqlite_storage::SqliteStorage;
+    use pomodorotimer::db::storage::Storage;
+    use pomodorotimer::db::timer_database::MIGRATIONS;
     use std::thread;
     use std::time::Duration;
 
+    fn new_timer(work_duration_sec: u64, break_duration_sec: u64) -> PomodoroTimer {
+        PomodoroTimer::new(work_duration_sec, break_duration_sec, MemoryStorage::new())
+    }
+
     // Timer runner tests
     #[test]
     fn should_new_timer_start_in_idle() {
         // Given a timer
-        let timer = PomodoroTimer::new(0, 0);
+        let timer = new_timer(0, 0);
 
         // When I ask for it's state
         let got = timer.get_state();
@@ -20,7 +39,7 @@ mod pomodoro_timer_tests {
     #[test]
     fn should_timer_end_in_idle_state() {
         // Given a timer
-        let mut timer = PomodoroTimer::new(0, 0);
+        let mut timer = new_timer(0, 0);
 
         // When I ask it to start and wait second
         timer.start_run();
@@ -33,7 +52,7 @@ mod pomodoro_timer_tests {
     #[test]
     fn should_pause_in_working() {
         // Given a timer
-        let mut timer = PomodoroTimer::new(2, 1);
+        let mut timer = new_timer(2, 1);
         timer.start_run();
         thread::sleep(Duration::from_secs(1));
 
@@ -47,7 +66,7 @@ mod pomodoro_timer_tests {
     #[test]
     fn should_pause_in_breaking() {
         // Given a timer
-        let mut timer = PomodoroTimer::new(0, 2);
+        let mut timer = new_timer(0, 2);
         timer.start_run();
         thread::sleep(Duration::from_secs(1));
 
@@ -61,7 +80,7 @@ mod pomodoro_timer_tests {
     #[test]
     fn should_pause_and_resume_stop_in_idle() {
         // Given a timer
-        let mut timer = PomodoroTimer::new(2, 1);
+        let mut timer = new_timer(2, 1);
         timer.start_run();
         thread::sleep(Duration::from_secs(1));
 
@@ -76,7 +95,7 @@ mod pomodoro_timer_tests {
     #[test]
     fn should_stop_end_in_idle() {
         // Given a timer
-        let mut timer = PomodoroTimer::new(10, 5);
+        let mut timer = new_timer(10, 5);
         timer.start_run();
 
         // When I ask it to stop
@@ -90,7 +109,7 @@ mod pomodoro_timer_tests {
     #[test]
     fn should_pause_stop_timer_runner() {
         // Given a timer
-        let mut timer = PomodoroTimer::new(10, 2);
+        let mut timer = new_timer(10, 2);
         timer.start_run();
 
         // When I ask it to pause
@@ -103,4 +122,525 @@ mod pomodoro_timer_tests {
         let time_rem_after = timer.get_remaining_time();
         assert!(time_rem_before - time_rem_after < Duration::from_secs(1));
     }
+
+    #[test]
+    fn should_skip_phases_and_record_the_time_actually_run() {
+        // Given a paused pomodoro of a signed in user
+        let mut timer = new_timer(60, 60);
+        timer.sign_in("alice", None).unwrap();
+        timer.start_run();
+        thread::sleep(Duration::from_millis(100));
+        timer.pause_timer();
+        assert!(timer.is_paused());
+
+        // When I skip the work and then the break
+        assert!(timer.skip_phase().is_ok());
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(timer.get_state(), Breaking);
+        assert!(!timer.is_paused());
+        assert!(timer.skip_phase().is_ok());
+        thread::sleep(Duration::from_millis(100));
+
+        // Then the run is over and recorded with next to no time
+        assert_eq!(timer.get_state(), Idle);
+        assert_eq!(timer.get_pomodoros_today(), 1);
+        assert_eq!(timer.get_total_time(Today), (0, 0));
+
+        // And there is nothing left to skip
+        assert_eq!(
+            timer.skip_phase(),
+            Err(String::from("There is no phase to skip"))
+        );
+    }
+
+    #[test]
+    fn should_extend_and_shorten_the_running_phase() {
+        // Given a one second pomodoro
+        let mut timer = new_timer(1, 0);
+        timer.sign_in("alice", None).unwrap();
+        timer.start_run();
+        thread::sleep(Duration::from_millis(100));
+
+        // When I extend it by two seconds
+        assert!(timer.extend_phase(Duration::from_secs(2)).is_ok());
+        thread::sleep(Duration::from_millis(1400));
+
+        // Then it is still running past its second
+        assert_eq!(timer.get_state(), Working);
+        assert_eq!(timer.get_phase_duration(), Duration::from_secs(3));
+
+        // And extending it further than a duration can count is refused, leaving it as it was
+        assert_eq!(
+            timer.extend_phase(Duration::MAX),
+            Err(String::from("The phase cannot get that long"))
+        );
+        assert_eq!(timer.get_phase_duration(), Duration::from_secs(3));
+
+        // And shortening it by more than is left ends it with the time it ran
+        assert!(timer.shorten_phase(Duration::from_secs(10)).is_ok());
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(timer.get_state(), Idle);
+        assert_eq!(timer.get_total_time(Today), (2, 0));
+        assert!(timer.extend_phase(Duration::from_secs(2)).is_err());
+    }
+
+    #[test]
+    fn should_record_completed_run_for_signed_in_user() {
+        // Given a signed in user
+        let mut timer = new_timer(1, 0);
+        assert!(timer.sign_in("alice", None).is_ok());
+
+        // When a run completes
+        timer.start_run();
+        thread::sleep(Duration::from_millis(1500));
+
+        // Then it counts towards the user's stats
+        assert_eq!(timer.get_total_time(Today), (1, 0));
+        assert_eq!(timer.get_users(), vec!["alice"]);
+    }
+
+    #[test]
+    fn should_correct_runs_and_undo_the_last_change() {
+        // Given a run added by hand
+        let mut timer = new_timer(0, 0);
+        timer.sign_in("alice", None).unwrap();
+        let run_id = timer.add_run(date("2026-10-01"), 25 * 60, 5 * 60).unwrap();
+
+        // When I edit it and undo the edit
+        assert!(timer
+            .edit_run(run_id, RunEdit::WorkingTime(50 * 60))
+            .is_ok());
+        assert_eq!(timer.get_recent_runs(1)[0].working_time_secs, 50 * 60);
+        assert_eq!(timer.undo(), Some(run_id));
+
+        // Then it is back as it was
+        let runs = timer.get_recent_runs(10);
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].working_time_secs, 25 * 60);
+
+        // And deleting it can be undone once
+        assert!(timer.delete_run(run_id));
+        assert!(timer.get_recent_runs(10).is_empty());
+        assert_eq!(timer.undo(), Some(run_id));
+        assert_eq!(timer.undo(), None);
+        assert_eq!(timer.get_recent_runs(10)[0].date, date("2026-10-01"));
+    }
+
+    #[test]
+    fn should_move_runs_to_another_user() {
+        // Given a run recorded as the wrong user
+        let mut timer = new_timer(0, 0);
+        timer.sign_in("alice", None).unwrap();
+        timer.sign_in("bob", None).unwrap();
+        let run_id = timer.add_run(date("2026-10-01"), 25 * 60, 5 * 60).unwrap();
+
+        // When it is moved to the right user
+        assert!(timer
+            .edit_run(run_id, RunEdit::User(String::from("alice")))
+            .is_ok());
+
+        // Then it is no longer the first user's to change
+        assert!(timer.get_recent_runs(10).is_empty());
+        assert!(!timer.delete_run(run_id));
+        timer.sign_in("alice", None).unwrap();
+        assert_eq!(timer.get_recent_runs(10)[0].id, run_id);
+    }
+
+    #[test]
+    fn should_only_move_runs_to_existing_users_without_a_password() {
+        // Given a run and a user with a password
+        let mut timer = new_timer(0, 0);
+        timer.sign_in("alice", None).unwrap();
+        timer.set_password(None, Some("secret")).unwrap();
+        timer.sign_in("bob", None).unwrap();
+        let run_id = timer.add_run(date("2026-10-01"), 25 * 60, 5 * 60).unwrap();
+
+        // When it is moved to a user who does not exist or to the one with a password
+        let to_nobody = timer.edit_run(run_id, RunEdit::User(String::from("alcie")));
+        let to_alice = timer.edit_run(run_id, RunEdit::User(String::from("alice")));
+
+        // Then both are refused, no user is created and the run stays
+        assert_eq!(to_nobody, Err(String::from("There is no user alcie")));
+        assert!(to_alice.is_err());
+        assert_eq!(timer.get_users(), vec!["alice", "bob"]);
+        assert_eq!(timer.get_recent_runs(10)[0].id, run_id);
+    }
+
+    #[test]
+    fn should_move_runs_to_the_other_users_task() {
+        // Given two runs on bob's report task, alice with a report task and carol without one
+        let mut storage = MemoryStorage::new();
+        let bob = storage.get_or_create_user("bob").id;
+        let alice = storage.get_or_create_user("alice").id;
+        storage.get_or_create_user("carol");
+        let report = storage.save_task(&bob, "report", &2, &day("2026-10-01")).id;
+        storage.save_task(&alice, "report", &4, &day("2026-10-01"));
+        let first = insert_run_on(&mut storage, &bob, "2026-10-01", 1500, &[], Some(&report));
+        let second = insert_run_on(&mut storage, &bob, "2026-10-02", 1500, &[], Some(&report));
+        let mut timer = PomodoroTimer::new(0, 0, storage);
+        timer.sign_in("bob", None).unwrap();
+
+        // When one run is moved to alice and the other to carol
+        assert!(timer
+            .edit_run(first, RunEdit::User(String::from("alice")))
+            .is_ok());
+        assert!(timer
+            .edit_run(second, RunEdit::User(String::from("carol")))
+            .is_ok());
+
+        // Then alice's run counts for her own task and carol's run has no task
+        timer.sign_in("alice", None).unwrap();
+        let tasks: Vec<(String, i64)> = timer
+            .get_tasks()
+            .into_iter()
+            .map(|(task, actual)| (task.name, actual))
+            .collect();
+        assert_eq!(tasks, vec![(String::from("report"), 1)]);
+        timer.sign_in("carol", None).unwrap();
+        assert_eq!(timer.get_recent_runs(10)[0].task, None);
+    }
+
+    #[test]
+    fn should_sign_in_ignoring_case_when_asked() {
+        // Given a user
+        let mut timer = new_timer(0, 0);
+        timer.sign_in("Nikolaj", None).unwrap();
+
+        // When signing in with another case, first exactly and then ignoring case
+        timer.sign_in("nikolaj", None).unwrap();
+        assert_eq!(timer.get_users(), vec!["Nikolaj", "nikolaj"]);
+        timer.set_ignore_name_case(true);
+        timer.sign_in("NIKOLAJ", None).unwrap();
+
+        // Then the exact name wins and no further user is created
+        assert_eq!(timer.get_username().as_deref(), Some("Nikolaj"));
+        timer.sign_in("nikolaj", None).unwrap();
+        assert_eq!(timer.get_username().as_deref(), Some("nikolaj"));
+        assert_eq!(timer.get_users().len(), 2);
+    }
+
+    #[test]
+    fn should_follow_merged_and_renamed_user() {
+        // Given someone signed in as a misspelled user
+        let mut timer = new_timer(0, 0);
+        timer.sign_in("nikolaj", None).unwrap();
+        timer.sign_in("Nikolaj", None).unwrap();
+        timer.add_run(date("2026-10-01"), 25 * 60, 5 * 60);
+
+        // When the user is merged into the right one, which is renamed after
+        assert!(timer.merge_users("Nikolaj", "nikolaj").is_ok());
+        assert!(timer.rename_user("nikolaj", "nik").is_ok());
+
+        // Then they are signed in as the remaining user, with the run
+        assert_eq!(timer.get_username().as_deref(), Some("nik"));
+        assert_eq!(timer.get_recent_runs(10).len(), 1);
+        assert!(timer.rename_user("nobody", "someone").is_err());
+    }
+
+    #[test]
+    fn should_ask_for_password_once_set() {
+        // Given a user who sets a password
+        let mut timer = new_timer(0, 0);
+        timer.sign_in("alice", None).unwrap();
+        timer.set_password(None, Some("correct horse")).unwrap();
+        assert!(timer.has_password());
+
+        // When signing in again, without and with the password
+        timer.sign_in("bob", None).unwrap();
+        assert_eq!(
+            timer.sign_in("alice", None),
+            Err(SignInError::PasswordRequired)
+        );
+        assert_eq!(
+            timer.sign_in("alice", Some("wrong")),
+            Err(SignInError::WrongPassword)
+        );
+        assert_eq!(timer.get_username().as_deref(), Some("bob"));
+
+        // Then only the right password signs them in, and others cannot take over the user
+        assert!(timer.rename_user("alice", "mallory").is_err());
+        assert!(timer.merge_users("alice", "bob").is_err());
+        assert!(timer.sign_in("alice", Some("correct horse")).is_ok());
+        assert!(timer.set_password(Some("wrong"), None).is_err());
+        timer.set_password(Some("correct horse"), None).unwrap();
+        timer.sign_in("bob", None).unwrap();
+        assert!(timer.sign_in("alice", None).is_ok());
+    }
+
+    #[test]
+    fn should_lock_out_after_repeated_wrong_passwords() {
+        // Given a user with a password
+        let mut timer = new_timer(0, 0);
+        timer.sign_in("alice", None).unwrap();
+        timer.set_password(None, Some("secret")).unwrap();
+
+        // When a wrong password is entered five times in a row
+        for _ in 1..MAX_FAILED_SIGN_INS {
+            assert_eq!(
+                timer.sign_in("alice", Some("guess")),
+                Err(SignInError::WrongPassword)
+            );
+        }
+        assert!(matches!(
+            timer.sign_in("alice", Some("guess")),
+            Err(SignInError::LockedOut(_))
+        ));
+
+        // Then even the right password is refused for now
+        assert!(matches!(
+            timer.sign_in("alice", Some("secret")),
+            Err(SignInError::LockedOut(_))
+        ));
+    }
+
+    #[test]
+    fn should_count_late_night_runs_for_the_day_before() {
+        // Given days starting at 04:00 in Copenhagen, two hours ahead of UTC in October
+        let settings = UserSettings {
+            day_start_hour: 4,
+            time_zone: Some(String::from("Europe/Copenhagen")),
+            ..UserSettings::default()
+        };
+        let at = |time: &str| DateTime::parse_from_rfc3339(time).unwrap().to_utc();
+
+        // Then a run ending at 01:30 local time counts for the evening before
+        assert_eq!(
+            settings.date_at(at("2026-10-18T23:30:00Z")),
+            date("2026-10-18")
+        );
+        assert_eq!(
+            settings.date_at(at("2026-10-19T02:00:00Z")),
+            date("2026-10-19")
+        );
+
+        // And without a day start the time zone alone decides
+        let new_york = UserSettings {
+            day_start_hour: 0,
+            time_zone: Some(String::from("America/New_York")),
+            ..UserSettings::default()
+        };
+        assert_eq!(
+            new_york.date_at(at("2026-10-19T02:00:00Z")),
+            date("2026-10-18")
+        );
+    }
+
+    #[test]
+    fn should_keep_day_settings_per_user() {
+        // Given a user who sets a day start and time zone
+        let mut timer = new_timer(0, 0);
+        assert!(timer.set_day_start_hour(4).is_err());
+        timer.sign_in("alice", None).unwrap();
+        assert!(timer.set_day_start_hour(24).is_err());
+        assert!(timer.set_time_zone(Some("Mars/Olympus_Mons")).is_err());
+        timer.set_day_start_hour(4).unwrap();
+        timer.set_time_zone(Some("Europe/Copenhagen")).unwrap();
+
+        // When someone else signs in, and then the user again
+        timer.sign_in("bob", None).unwrap();
+        assert_eq!(timer.get_user_settings(), UserSettings::default());
+        timer.sign_in("alice", None).unwrap();
+
+        // Then the user's settings are back
+        let settings = timer.get_user_settings();
+        assert_eq!(settings.day_start_hour, 4);
+        assert_eq!(settings.time_zone.as_deref(), Some("Europe/Copenhagen"));
+    }
+
+    #[test]
+    fn should_create_tasks_on_the_users_day() {
+        // Given a user far east whose days start late in the evening
+        let mut timer = new_timer(0, 0);
+        timer.sign_in("alice", None).unwrap();
+        timer.set_time_zone(Some("Pacific/Kiritimati")).unwrap();
+        timer.set_day_start_hour(23).unwrap();
+
+        // When they create a task
+        timer.set_task("report", None);
+
+        // Then it is created on the user's day rather than the computer's
+        let (task, _) = timer.get_tasks().pop().unwrap();
+        assert_eq!(task.created, timer.get_user_settings().today());
+    }
+
+    #[test]
+    fn should_load_durations_when_signing_in() {
+        // Given a user who changes the durations and week start
+        let mut timer = new_timer(25 * 60, 5 * 60);
+        timer.sign_in("alice", None).unwrap();
+        timer.set_state_time_period(Duration::from_secs(50 * 60), Working);
+        timer.set_state_time_period(Duration::from_secs(10 * 60), Breaking);
+        timer.set_week_start(Weekday::Sun);
+
+        // When someone else signs in, and then the user again
+        timer.sign_in("bob", None).unwrap();
+        assert_eq!(timer.get_work_duration(), Duration::from_secs(25 * 60));
+        assert_eq!(timer.get_break_duration(), Duration::from_secs(5 * 60));
+        assert_eq!(timer.get_week_start(), Weekday::Mon);
+        timer.sign_in("alice", None).unwrap();
+
+        // Then the user's own settings are back
+        assert_eq!(timer.get_work_duration(), Duration::from_secs(50 * 60));
+        assert_eq!(timer.get_break_duration(), Duration::from_secs(10 * 60));
+        assert_eq!(timer.get_week_start(), Weekday::Sun);
+        assert_eq!(timer.get_user_settings().work_duration_secs, Some(50 * 60));
+    }
+
+    #[test]
+    fn should_take_long_break_every_nth_pomodoro() {
+        // Given a user taking a long break after every pomodoro, without notifications
+        let mut timer = new_timer(0, 0);
+        assert!(timer.set_auto_start(true).is_err());
+        timer.sign_in("alice", None).unwrap();
+        timer.set_notifications(false).unwrap();
+        timer.set_long_break(Duration::from_secs(1), 1).unwrap();
+
+        // When a pomodoro is done
+        timer.start_run();
+        thread::sleep(Duration::from_millis(300));
+
+        // Then the break is the long one instead of none
+        assert_eq!(timer.get_state(), Breaking);
+        assert_eq!(timer.get_phase_duration(), Duration::from_secs(1));
+        assert_eq!(timer.get_cycle_progress(), Some((0, 1)));
+        thread::sleep(Duration::from_secs(1));
+        assert_eq!(timer.get_state(), Idle);
+        assert_eq!(timer.get_recent_runs(10).len(), 1);
+        assert_eq!(timer.get_pomodoros_today(), 1);
+    }
+
+    #[test]
+    fn should_start_next_pomodoro_by_itself_when_asked() {
+        // Given a user with auto start
+        let mut timer = new_timer(1, 0);
+        timer.sign_in("alice", None).unwrap();
+        timer.set_notifications(false).unwrap();
+        timer.set_auto_start(true).unwrap();
+
+        // When the timer runs for a bit more than two pomodoros
+        timer.start_run();
+        thread::sleep(Duration::from_millis(2500));
+
+        // Then both were recorded and the third one is going
+        assert_eq!(timer.get_state(), Working);
+        assert_eq!(timer.get_recent_runs(10).len(), 2);
+        timer.stop_timer();
+    }
+
+    fn date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn should_compute_period_date_ranges() {
+        // Given a Thursday
+        let today = date("2026-10-15");
+
+        // When I ask for the dates of each period
+        let range = |period: Period| period.date_range(today, Weekday::Mon);
+
+        // Then they end today or before and start where expected
+        assert_eq!(
+            range(Period::Yesterday),
+            Some((date("2026-10-14"), date("2026-10-14")))
+        );
+        assert_eq!(range(Period::ThisWeek), Some((date("2026-10-12"), today)));
+        assert_eq!(
+            range(Period::LastWeek),
+            Some((date("2026-10-05"), date("2026-10-11")))
+        );
+        assert_eq!(range(Period::ThisMonth), Some((date("2026-10-01"), today)));
+        assert_eq!(range(Period::ThisYear), Some((date("2026-01-01"), today)));
+        assert_eq!(
+            range(Period::LastNDays(7)),
+            Some((date("2026-10-09"), today))
+        );
+        assert_eq!(range(Period::AllTime), None);
+    }
+
+    #[test]
+    fn should_sum_all_runs_for_more_days_than_there_are_dates() {
+        // Given a run today, in memory and in SQLite
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.run_pending_migrations(MIGRATIONS).unwrap();
+        let timers = [
+            new_timer(0, 0),
+            PomodoroTimer::new(0, 0, SqliteStorage::new(conn)),
+        ];
+        for mut timer in timers {
+            timer.sign_in("alice", None).unwrap();
+            let today = timer.get_user_settings().today();
+            timer.add_run(today, 1500, 300);
+
+            // When I ask for the last four billion days
+            let period = Period::LastNDays(4_000_000_000);
+
+            // Then the range starts at the first date there is and holds the run
+            assert_eq!(
+                period.date_range(today, Weekday::Mon),
+                Some((NaiveDate::MIN, today))
+            );
+            assert_eq!(timer.get_total_time(period), (1500, 300));
+        }
+    }
+
+    #[test]
+    fn should_start_week_on_configured_day() {
+        // Given a Thursday and weeks starting on Sunday
+        let today = date("2026-10-15");
+
+        // When I ask for this and last week
+        let this_week = Period::ThisWeek.date_range(today, Weekday::Sun);
+        let last_week = Period::LastWeek.date_range(today, Weekday::Sun);
+
+        // Then they start on Sundays
+        assert_eq!(this_week, Some((date("2026-10-11"), today)));
+        assert_eq!(last_week, Some((date("2026-10-04"), date("2026-10-10"))));
+    }
+
+    #[test]
+    fn should_fill_dashboard_with_days_and_weeks_without_runs() {
+        // Given runs today, yesterday and three weeks ago
+        let mut timer = new_timer(0, 0);
+        timer.sign_in("alice", None).unwrap();
+        let today = timer.get_user_settings().today();
+        timer.add_run(today, 1500, 300);
+        timer.add_run(today - Days::new(1), 3000, 600);
+        timer.add_run(today - Days::new(21), 600, 0);
+
+        // When I look at the dashboard
+        let dashboard = timer.get_dashboard();
+
+        // Then every recent day is there, the empty ones as zero
+        assert_eq!(dashboard.days.len(), DASHBOARD_DAYS as usize);
+        assert_eq!(dashboard.days.last(), Some(&(today, 1500)));
+        assert_eq!(dashboard.days[0], (today - Days::new(13), 0));
+        assert_eq!(dashboard.daily_average(), 4500 / 14);
+        assert_eq!(dashboard.best_day(), Some((today - Days::new(1), 3000)));
+
+        // And the weeks add up all three runs, ending with the current week
+        assert_eq!(dashboard.weeks.len(), DASHBOARD_WEEKS as usize);
+        assert_eq!(dashboard.weeks.iter().sum::<i64>(), 5100);
+        assert!(*dashboard.weeks.last().unwrap() >= 1500);
+        assert!(dashboard.tasks.is_empty());
+    }
+
+    #[test]
+    fn should_sum_work_per_day_of_a_year() {
+        // Given two runs on New Year's Eve and one the day after
+        let mut timer = new_timer(0, 0);
+        timer.sign_in("alice", None).unwrap();
+        timer.add_run(date("2025-12-31"), 1500, 300);
+        timer.add_run(date("2025-12-31"), 1200, 300);
+        timer.add_run(date("2026-01-01"), 600, 0);
+
+        // When I ask for the days of 2025
+        let days = timer.get_daily_work_time(2025);
+
+        // Then only that year's day is there, with both runs added up
+        assert_eq!(
+            days.into_iter().collect::<Vec<_>>(),
+            vec![(date("2025-12-31"), 2700)]
+        );
+    }
 }
@@ -0,0 +1,315 @@
+mod common;
+
+mod storage_tests {
+    use crate::common::{day, insert_run_on, TestRun};
+    use chrono::{NaiveDate, Weekday};
+    use diesel::{Connection, SqliteConnection};
+    use diesel_migrations::MigrationHarness;
+    use pomodorotimer::db::memory_storage::MemoryStorage;
+    use pomodorotimer::db::sqlite_storage::SqliteStorage;
+    use pomodorotimer::db::storage::Storage;
+    use pomodorotimer::db::timer_database::{Grouping, MIGRATIONS};
+    use std::panic::{self, AssertUnwindSafe};
+
+    fn backends() -> Vec<Box<dyn Storage>> {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.run_pending_migrations(MIGRATIONS).unwrap();
+        vec![
+            Box::new(SqliteStorage::new(conn)),
+            Box::new(MemoryStorage::new()),
+        ]
+    }
+
+    #[test]
+    fn should_behave_the_same_in_every_backend() {
+        for mut storage in backends() {
+            let storage = storage.as_mut();
+
+            // Given two users, a task and a few tagged runs
+            let alice = storage.get_or_create_user("alice").id;
+            let bob = storage.get_or_create_user("bob").id;
+            let task = storage.save_task(&alice, "report", &2, &day("2026-10-01"));
+            insert_run_on(storage, &alice, "2026-09-30", 600, &["review"], None);
+            insert_run_on(
+                storage,
+                &alice,
+                "2026-10-01",
+                1500,
+                &["backend"],
+                Some(&task.id),
+            );
+            let last = insert_run_on(storage, &alice, "2026-10-01", 1200, &[], None);
+            insert_run_on(storage, &bob, "2026-10-01", 3000, &["backend"], None);
+            storage.set_timer_run_note(&last, "focused");
+
+            // Then users and tasks are looked up the same way
+            assert_eq!(storage.get_or_create_user("alice").id, alice);
+            assert_eq!(storage.get_users(), vec!["alice", "bob"]);
+            assert_eq!(
+                storage
+                    .save_task(&alice, "report", &3, &day("2026-10-01"))
+                    .id,
+                task.id
+            );
+            assert_eq!(
+                storage
+                    .get_task(&alice, "report")
+                    .unwrap()
+                    .estimated_pomodoros,
+                3
+            );
+            assert!(storage.get_task(&bob, "report").is_none());
+            let actuals: Vec<(String, i64)> = storage
+                .get_tasks_with_actuals(&alice)
+                .into_iter()
+                .map(|(task, actual)| (task.name, actual))
+                .collect();
+            assert_eq!(actuals, vec![("report".to_string(), 1)]);
+
+            // And runs are recorded and summed up the same way
+            assert_eq!(storage.get_last_timer_run_id(&alice), Some(last));
+            let records = storage.get_run_records(&alice, None);
+            assert_eq!(records.len(), 3);
+            assert_eq!(records[1].task.as_deref(), Some("report"));
+            assert_eq!(records[2].note.as_deref(), Some("focused"));
+
+            let october = Some((
+                NaiveDate::from_ymd_opt(2026, 10, 1).unwrap(),
+                NaiveDate::from_ymd_opt(2026, 10, 31).unwrap(),
+            ));
+            assert_eq!(
+                storage.sum_timer_runs(&alice, None, None).working_time_secs,
+                3300
+            );
+            assert_eq!(storage.sum_timer_runs(&alice, october, None).runs, 2);
+            assert_eq!(
+                storage
+                    .sum_timer_runs(&alice, None, Some("backend"))
+                    .working_time_secs,
+                1500
+            );
+
+            let tags: Vec<(String, i64)> = storage
+                .get_tag_run_totals(&alice, None)
+                .into_iter()
+                .map(|group| (group.key, group.totals.runs))
+                .collect();
+            assert_eq!(
+                tags,
+                vec![("backend".to_string(), 1), ("review".to_string(), 1)]
+            );
+
+            let tasks: Vec<(String, i64)> = storage
+                .get_task_run_totals(&alice, october)
+                .into_iter()
+                .map(|group| (group.key, group.totals.working_time_secs))
+                .collect();
+            assert_eq!(tasks, vec![("report".to_string(), 1500)]);
+
+            let months: Vec<(String, i64)> = storage
+                .get_grouped_run_totals(&alice, None, Grouping::Month)
+                .into_iter()
+                .map(|group| (group.key, group.totals.working_time_secs))
+                .collect();
+            assert_eq!(
+                months,
+                vec![("2026-09".to_string(), 600), ("2026-10".to_string(), 2700)]
+            );
+        }
+    }
+
+    #[test]
+    fn should_merge_users_the_same_in_every_backend() {
+        for mut storage in backends() {
+            let storage = storage.as_mut();
+
+            // Given two spellings of a user, both with a report task
+            let typo = storage.get_or_create_user("Nikolaj").id;
+            let nikolaj = storage.get_or_create_user("nikolaj").id;
+            let typo_report = storage.save_task(&typo, "report", &2, &day("2026-10-01"));
+            storage.save_task(&nikolaj, "report", &3, &day("2026-10-01"));
+            insert_run_on(
+                storage,
+                &typo,
+                "2026-10-01",
+                1500,
+                &["db"],
+                Some(&typo_report.id),
+            );
+
+            // When the misspelled one is merged into the other and then renamed
+            assert!(storage.merge_users("Nikolaj", "bob").is_err());
+            assert!(storage.merge_users("Nikolaj", "nikolaj").is_ok());
+            assert!(storage.rename_user("nikolaj", "nik").is_ok());
+
+            // Then one user is left with the run on the remaining task
+            assert_eq!(storage.get_users(), vec!["nik"]);
+            let records = storage.get_run_records(&nikolaj, None);
+            assert_eq!(records.len(), 1);
+            assert_eq!(records[0].user, "nik");
+            assert_eq!(records[0].task.as_deref(), Some("report"));
+            assert_eq!(storage.get_tasks_with_actuals(&nikolaj).len(), 1);
+
+            // And new users do not reuse the deleted user's id
+            assert_ne!(storage.get_or_create_user("eve").id, nikolaj);
+        }
+    }
+
+    #[test]
+    fn should_not_reuse_ids_in_any_backend() {
+        for mut storage in backends() {
+            let storage = storage.as_mut();
+
+            // Given the newest run, user and task are gone
+            let alice = storage.get_or_create_user("alice").id;
+            let bob = storage.get_or_create_user("bob").id;
+            storage.save_task(&alice, "report", &3, &day("2026-10-01"));
+            let report = storage.save_task(&bob, "report", &2, &day("2026-10-02"));
+            insert_run_on(storage, &alice, "2026-10-01", 600, &[], None);
+            let deleted = insert_run_on(storage, &alice, "2026-10-02", 900, &[], None);
+            assert!(storage.delete_timer_run(&deleted));
+            assert!(storage.merge_users("bob", "alice").is_ok());
+
+            // When more are added
+            let run = insert_run_on(storage, &alice, "2026-10-03", 1200, &[], None);
+            let user = storage.get_or_create_user("carol").id;
+            let task = storage.save_task(&alice, "slides", &1, &day("2026-10-03"));
+
+            // Then they get new ids, and tasks keep the day they were created on
+            assert!(run > deleted);
+            assert!(user > bob);
+            assert!(task.id > report.id);
+            assert_eq!(task.created, day("2026-10-03"));
+        }
+    }
+
+    #[test]
+    fn should_refuse_duplicate_uuids_in_every_backend() {
+        for mut storage in backends() {
+            let storage = storage.as_mut();
+
+            // Given a run
+            let alice = storage.get_or_create_user("alice").id;
+            let run = TestRun::new(&alice, "2026-10-01", 600);
+            storage.insert_timer_run(&run.new_timer_run(), &[]);
+
+            // When it is saved again with the same uuid
+            let again = panic::catch_unwind(AssertUnwindSafe(|| {
+                storage.insert_timer_run(&run.new_timer_run(), &[])
+            }));
+
+            // Then it is refused
+            assert!(again.is_err());
+            assert_eq!(storage.sum_timer_runs(&alice, None, None).runs, 1);
+        }
+    }
+
+    #[test]
+    fn should_refuse_renames_the_same_in_every_backend() {
+        for mut storage in backends() {
+            let storage = storage.as_mut();
+
+            // Given two users
+            storage.get_or_create_user("alice");
+            storage.get_or_create_user("bob");
+
+            // When a missing user is renamed to a taken name, or a user to a taken name
+            let missing = storage.rename_user("nobody", "alice");
+            let taken = storage.rename_user("bob", "alice");
+
+            // Then the missing user is reported first, and no one is renamed
+            assert_eq!(missing, Err(String::from("There is no user nobody")));
+            assert_eq!(
+                taken,
+                Err(String::from(
+                    "alice already exists, merge the users instead"
+                ))
+            );
+            assert_eq!(storage.get_users(), vec!["alice", "bob"]);
+        }
+    }
+
+    #[test]
+    fn should_save_credentials_the_same_in_every_backend() {
+        for mut storage in backends() {
+            let storage = storage.as_mut();
+
+            // Given a user who was locked out after setting a password
+            let alice = storage.get_or_create_user("alice").id;
+            let locked_until = NaiveDate::from_ymd_opt(2026, 10, 18)
+                .unwrap()
+                .and_hms_opt(12, 5, 0)
+                .unwrap();
+            storage.save_password_hash(&alice, Some("$argon2id$hash"));
+            storage.save_failed_sign_ins(&alice, &3, Some(&locked_until));
+
+            // Then the user comes back with them
+            let user = storage.get_or_create_user("alice");
+            assert_eq!(user.password_hash.as_deref(), Some("$argon2id$hash"));
+            assert_eq!(user.failed_sign_ins, 3);
+            assert_eq!(user.locked_until, Some(locked_until));
+
+            // And a new password starts over
+            storage.save_password_hash(&alice, None);
+            let user = storage.get_or_create_user("alice");
+            assert_eq!(user.password_hash, None);
+            assert_eq!((user.failed_sign_ins, user.locked_until), (0, None));
+        }
+    }
+
+    #[test]
+    fn should_normalize_tags_the_same_in_every_backend() {
+        for mut storage in backends() {
+            let storage = storage.as_mut();
+
+            // Given runs tagged in different cases and with stray spaces
+            let alice = storage.get_or_create_user("alice").id;
+            let first = insert_run_on(storage, &alice, "2026-10-01", 600, &["Work", "work "], None);
+            insert_run_on(storage, &alice, "2026-10-02", 900, &["work"], None);
+
+            // Then they are one tag, stored lowercase
+            assert_eq!(storage.get_timer_run(&first).unwrap().1, vec!["work"]);
+            let tags: Vec<(String, i64)> = storage
+                .get_tag_run_totals(&alice, None)
+                .into_iter()
+                .map(|group| (group.key, group.totals.working_time_secs))
+                .collect();
+            assert_eq!(tags, vec![("work".to_string(), 1500)]);
+
+            // And stats for the tag are found in any case
+            assert_eq!(storage.sum_timer_runs(&alice, None, Some("WORK")).runs, 2);
+        }
+    }
+
+    #[test]
+    fn should_group_weeks_from_the_week_start_in_every_backend() {
+        for mut storage in backends() {
+            let storage = storage.as_mut();
+
+            // Given runs on a Saturday, Sunday and Monday
+            let alice = storage.get_or_create_user("alice").id;
+            for date in ["2026-10-03", "2026-10-04", "2026-10-05"] {
+                insert_run_on(storage, &alice, date, 600, &[], None);
+            }
+
+            // When they are grouped by weeks starting on Monday or on Sunday
+            let mut weeks = |week_start| -> Vec<(String, i64)> {
+                storage
+                    .get_grouped_run_totals(&alice, None, Grouping::Week(week_start))
+                    .into_iter()
+                    .map(|group| (group.key, group.totals.runs))
+                    .collect()
+            };
+
+            // Then each week is keyed by its first day
+            assert_eq!(
+                weeks(Weekday::Mon),
+                vec![("2026-09-28".to_string(), 2), ("2026-10-05".to_string(), 1)]
+            );
+            assert_eq!(
+                weeks(Weekday::Sun),
+                vec![("2026-09-27".to_string(), 1), ("2026-10-04".to_string(), 2)]
+            );
+        }
+    }
+}
@@ -0,0 +1,501 @@
+mod common;
+
+mod timer_database_tests {
+    use crate::common::{day, TestRun};
+    use chrono::{Local, NaiveDate};
+    use diesel::{sql_query, Connection, RunQueryDsl, SqliteConnection};
+    use diesel_migrations::MigrationHarness;
+    use pomodorotimer::core::models::{RunTotals, UserSettings};
+    use pomodorotimer::db::timer_database::{
+        add_tags_to_timer_run, create_timer_run, delete_timer_run, establish_connection,
+        get_grouped_run_totals, get_last_timer_run_id, get_or_create_user, get_run_records,
+        get_tag_run_totals, get_tagged_timer_runs, get_tasks_with_actuals, get_timer_run,
+        get_timer_run_tags, get_timer_runs, get_users, insert_timer_run, is_postgres_url,
+        merge_users, normalize_stored_tags, reinsert_timer_run, rename_user, resolve_database_path,
+        save_task, save_user_settings, set_timer_run_note, sum_timer_runs, update_timer_run,
+        write_run_records, ExportFormat, Grouping, MIGRATIONS,
+    };
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn insert_run_on(conn: &mut SqliteConnection, user_id: &i32, date: &str, working: i32) -> i32 {
+        insert_timer_run(conn, &TestRun::new(user_id, date, working).new_timer_run())
+    }
+
+    fn in_memory_connection() -> SqliteConnection {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.run_pending_migrations(MIGRATIONS).unwrap();
+        conn
+    }
+
+    #[test]
+    fn should_tag_timer_run() {
+        // Given a recorded run
+        let conn = &mut in_memory_connection();
+        let alice = get_or_create_user(conn, "alice").id;
+        let run_id = create_timer_run(conn, &alice, &1500, &300, None, None);
+
+        // When I tag it twice with an overlapping set of tags
+        add_tags_to_timer_run(
+            conn,
+            &run_id,
+            &["backend".to_string(), "review".to_string()],
+        );
+        add_tags_to_timer_run(conn, &run_id, &["backend".to_string()]);
+
+        // Then each tag should be attached once
+        let mut tags: Vec<String> = get_tagged_timer_runs(conn, &alice)
+            .into_iter()
+            .map(|(_, tag)| tag)
+            .collect();
+        tags.sort();
+        assert_eq!(tags, vec!["backend", "review"]);
+    }
+
+    #[test]
+    fn should_leave_out_untagged_runs() {
+        // Given a tagged and an untagged run
+        let conn = &mut in_memory_connection();
+        let alice = get_or_create_user(conn, "alice").id;
+        let run_id = create_timer_run(conn, &alice, &1500, &300, None, None);
+        add_tags_to_timer_run(conn, &run_id, &["backend".to_string()]);
+        create_timer_run(conn, &alice, &600, &60, None, None);
+
+        // When I ask for the tagged runs
+        let tagged = get_tagged_timer_runs(conn, &alice);
+
+        // Then only the tagged run is returned
+        assert_eq!(get_timer_runs(conn, &alice).len(), 2);
+        assert_eq!(tagged.len(), 1);
+        assert_eq!(tagged[0].0.id, run_id);
+    }
+
+    #[test]
+    fn should_attach_note_to_last_run() {
+        // Given two recorded runs
+        let conn = &mut in_memory_connection();
+        let alice = get_or_create_user(conn, "alice").id;
+        create_timer_run(conn, &alice, &1500, &300, Some("first"), None);
+        let last_id = create_timer_run(conn, &alice, &1500, &300, None, None);
+
+        // When I attach a note to the last one
+        let got = get_last_timer_run_id(conn, &alice);
+        set_timer_run_note(conn, &got.unwrap(), "wrote the parser");
+
+        // Then only the last run has the new note
+        assert_eq!(got, Some(last_id));
+        let notes: Vec<Option<String>> = get_timer_runs(conn, &alice)
+            .into_iter()
+            .map(|tr| tr.note)
+            .collect();
+        assert_eq!(
+            notes,
+            vec![
+                Some("first".to_string()),
+                Some("wrote the parser".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn should_delete_and_reinsert_run_with_tags() {
+        // Given a tagged run
+        let conn = &mut in_memory_connection();
+        let alice = get_or_create_user(conn, "alice").id;
+        let run_id = create_timer_run(conn, &alice, &1500, &300, Some("first"), None);
+        add_tags_to_timer_run(conn, &run_id, &["review".to_string()]);
+        let run = get_timer_run(conn, &run_id).unwrap();
+
+        // When I delete it
+        assert!(delete_timer_run(conn, &run_id));
+        assert!(!delete_timer_run(conn, &run_id));
+
+        // Then neither the run nor its tags are left
+        assert!(get_timer_run(conn, &run_id).is_none());
+        assert!(get_timer_run_tags(conn, &run_id).is_empty());
+
+        // And reinserting brings both back with the same id
+        reinsert_timer_run(conn, &run, &["review".to_string()]);
+        assert_eq!(get_timer_run(conn, &run_id), Some(run));
+        assert_eq!(get_timer_run_tags(conn, &run_id), vec!["review"]);
+    }
+
+    #[test]
+    fn should_update_whole_run() {
+        // Given a run with a note
+        let conn = &mut in_memory_connection();
+        let alice = get_or_create_user(conn, "alice").id;
+        let run_id = create_timer_run(conn, &alice, &1500, &300, Some("first"), None);
+
+        // When I replace it with one without a note and another date
+        let mut run = get_timer_run(conn, &run_id).unwrap();
+        run.note = None;
+        run.date = NaiveDate::from_ymd_opt(2026, 1, 2).unwrap();
+        update_timer_run(conn, &run);
+
+        // Then the note is cleared and the date changed
+        assert_eq!(get_timer_run(conn, &run_id), Some(run));
+    }
+
+    #[test]
+    fn should_count_completed_pomodoros_per_task() {
+        // Given two tasks where only one has been worked on
+        let conn = &mut in_memory_connection();
+        let alice = get_or_create_user(conn, "alice").id;
+        let parser = save_task(conn, &alice, "parser", &2, &day("2026-10-01"));
+        save_task(conn, &alice, "docs", &1, &day("2026-10-01"));
+        create_timer_run(conn, &alice, &1500, &300, None, Some(&parser.id));
+        create_timer_run(conn, &alice, &1500, &300, None, Some(&parser.id));
+        create_timer_run(conn, &alice, &1500, &300, None, None);
+
+        // When I re-estimate a task and ask for the actuals
+        let updated = save_task(conn, &alice, "parser", &3, &day("2026-10-01"));
+        let tasks: Vec<(String, i32, i64)> = get_tasks_with_actuals(conn, &alice)
+            .into_iter()
+            .map(|(task, actual)| (task.name, task.estimated_pomodoros, actual))
+            .collect();
+
+        // Then the task keeps its id and the counts only include its own runs
+        assert_eq!(updated.id, parser.id);
+        assert_eq!(
+            tasks,
+            vec![("parser".to_string(), 3, 2), ("docs".to_string(), 1, 0)]
+        );
+    }
+
+    #[test]
+    fn should_create_user_once() {
+        // Given a database
+        let conn = &mut in_memory_connection();
+
+        // When the same user signs in twice
+        let first = get_or_create_user(conn, "alice");
+        let second = get_or_create_user(conn, "alice");
+
+        // Then only one user exists, even without any runs
+        assert_eq!(first.id, second.id);
+        assert_eq!(get_users(conn), vec!["alice"]);
+    }
+
+    #[test]
+    fn should_save_user_settings() {
+        // Given a user without settings
+        let conn = &mut in_memory_connection();
+        let alice = get_or_create_user(conn, "alice");
+        assert_eq!(alice.get_settings(), UserSettings::default());
+
+        // When I save some
+        let settings = UserSettings {
+            day_start_hour: 4,
+            time_zone: Some(String::from("Europe/Copenhagen")),
+            ..UserSettings::default()
+        };
+        save_user_settings(conn, &alice.id, &settings);
+
+        // Then they are read back with the user
+        assert_eq!(get_or_create_user(conn, "alice").get_settings(), settings);
+
+        // And settings saved before newer ones existed get defaults for those
+        let old: UserSettings = serde_json::from_str(r#"{"day_start_hour":4}"#).unwrap();
+        assert_eq!(old.day_start_hour, 4);
+        assert!(old.notifications && old.reflection_prompt);
+        assert_eq!(old.work_duration_secs, None);
+    }
+
+    #[test]
+    fn should_rename_user_keeping_history() {
+        // Given a user with a run, and another user
+        let conn = &mut in_memory_connection();
+        let nikolaj = get_or_create_user(conn, "Nikolaj").id;
+        create_timer_run(conn, &nikolaj, &1500, &300, None, None);
+        get_or_create_user(conn, "alice");
+
+        // When I rename them, also to a name that is taken
+        assert!(rename_user(conn, "Nikolaj", "nikolaj").is_ok());
+        assert!(rename_user(conn, "nikolaj", "alice").is_err());
+        assert!(rename_user(conn, "bob", "robert").is_err());
+
+        // Then only the first rename happened and the run stays with the user
+        assert_eq!(get_users(conn), vec!["alice", "nikolaj"]);
+        assert_eq!(get_or_create_user(conn, "nikolaj").id, nikolaj);
+        assert_eq!(get_timer_runs(conn, &nikolaj).len(), 1);
+    }
+
+    #[test]
+    fn should_merge_users_and_their_tasks() {
+        // Given two users who both have runs on a task with the same name
+        let conn = &mut in_memory_connection();
+        let typo = get_or_create_user(conn, "Nikolaj").id;
+        let nikolaj = get_or_create_user(conn, "nikolaj").id;
+        let typo_report = save_task(conn, &typo, "report", &2, &day("2026-10-01")).id;
+        let typo_slides = save_task(conn, &typo, "slides", &1, &day("2026-10-01")).id;
+        let report = save_task(conn, &nikolaj, "report", &3, &day("2026-10-01")).id;
+        create_timer_run(conn, &typo, &1500, &300, None, Some(&typo_report));
+        create_timer_run(conn, &typo, &1500, &300, None, Some(&typo_slides));
+        create_timer_run(conn, &nikolaj, &1500, &300, None, Some(&report));
+
+        // When I merge the misspelled user into the other one
+        assert!(merge_users(conn, "Nikolaj", "Nikolaj").is_err());
+        assert!(merge_users(conn, "Nikolaj", "nikolaj").is_ok());
+
+        // Then all runs and tasks belong to the remaining user, with the same tasks merged
+        assert_eq!(get_users(conn), vec!["nikolaj"]);
+        assert_eq!(get_timer_runs(conn, &nikolaj).len(), 3);
+        let tasks: Vec<(String, i32, i64)> = get_tasks_with_actuals(conn, &nikolaj)
+            .into_iter()
+            .map(|(task, actual)| (task.name, task.estimated_pomodoros, actual))
+            .collect();
+        assert_eq!(
+            tasks,
+            vec![
+                (String::from("slides"), 1, 1),
+                (String::from("report"), 3, 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn should_migrate_user_names_to_users() {
+        // Given a database from before the users table with runs by name
+        let conn = &mut SqliteConnection::establish(":memory:").unwrap();
+        for _ in 0..3 {
+            conn.run_next_migration(MIGRATIONS).unwrap();
+        }
+        sql_query(
+            "INSERT INTO timer_runs (user, working_time_secs, breaking_time_secs, date) \
+             VALUES ('bob', 1500, 300, '2025-01-20'), ('alice', 600, 60, '2025-01-21')",
+        )
+        .execute(conn)
+        .unwrap();
+
+        // When the remaining migrations run
+        conn.run_pending_migrations(MIGRATIONS).unwrap();
+
+        // Then the names become users owning their runs
+        assert_eq!(get_users(conn), vec!["alice", "bob"]);
+        let bob = get_or_create_user(conn, "bob").id;
+        let runs = get_timer_runs(conn, &bob);
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].working_time_secs, 1500);
+    }
+
+    #[test]
+    fn should_migrate_tags_to_lowercase() {
+        // Given a database from before tags were normalized with the same tag in two cases,
+        // also outside of ASCII
+        let conn = &mut SqliteConnection::establish(":memory:").unwrap();
+        for _ in 0..8 {
+            conn.run_next_migration(MIGRATIONS).unwrap();
+        }
+        let alice = get_or_create_user(conn, "alice").id;
+        for query in [
+            format!(
+                "INSERT INTO timer_runs (user_id, working_time_secs, breaking_time_secs, date, uuid) \
+                 VALUES ({alice}, 1500, 300, '2026-10-01', 'a'), ({alice}, 600, 60, '2026-10-02', 'b')"
+            ),
+            String::from(
+                "INSERT INTO tags (name) VALUES ('Work'), ('work'), (' Review'), ('Ærø'), ('ærø')",
+            ),
+            String::from(
+                "INSERT INTO timer_run_tags (timer_run_id, tag_id) \
+                 VALUES (1, 1), (1, 2), (2, 2), (2, 3), (1, 4), (2, 4), (2, 5)",
+            ),
+        ] {
+            sql_query(query).execute(conn).unwrap();
+        }
+
+        // When the remaining migrations run and the stored tags are normalized
+        conn.run_pending_migrations(MIGRATIONS).unwrap();
+        normalize_stored_tags(conn);
+
+        // Then the runs keep one lowercase tag each
+        assert_eq!(get_timer_run_tags(conn, &1), vec!["work", "ærø"]);
+        assert_eq!(get_timer_run_tags(conn, &2), vec!["review", "work", "ærø"]);
+    }
+
+    #[test]
+    fn should_prefer_database_flag() {
+        // Given a path from the command line
+        let path = resolve_database_path(Some("work.db".to_string()));
+
+        // Then it is used as is
+        assert_eq!(path, PathBuf::from("work.db"));
+    }
+
+    #[test]
+    fn should_tell_postgres_urls_from_paths() {
+        // Given database locations from the command line
+        let url = resolve_database_path(Some("postgres://alice@server/pomodoro".to_string()));
+        let path = resolve_database_path(Some("postgres/pomodoro.db".to_string()));
+
+        // Then only the URL is a PostgreSQL database
+        assert!(is_postgres_url(&url));
+        assert!(is_postgres_url(&PathBuf::from(
+            "postgresql://server/pomodoro"
+        )));
+        assert!(!is_postgres_url(&path));
+    }
+
+    #[test]
+    fn should_create_database_directories() {
+        // Given a database path in a directory that does not exist yet
+        let dir = env::temp_dir().join(format!("pomodorotimer-test-{}", std::process::id()));
+        let path = dir.join("nested").join("pomodoro.db");
+
+        // When I connect to it
+        let conn = &mut establish_connection(&path);
+
+        // Then the directories and a migrated database are created
+        assert!(path.exists());
+        assert!(get_users(conn).is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn should_export_runs_with_tags_and_task() {
+        // Given a tagged run on a task
+        let conn = &mut in_memory_connection();
+        let alice = get_or_create_user(conn, "alice").id;
+        let task = save_task(conn, &alice, "parser", &2, &day("2026-10-01"));
+        let run_id = create_timer_run(
+            conn,
+            &alice,
+            &1500,
+            &300,
+            Some("done, mostly"),
+            Some(&task.id),
+        );
+        add_tags_to_timer_run(
+            conn,
+            &run_id,
+            &["review".to_string(), "backend".to_string()],
+        );
+
+        // When I export it as CSV
+        let records = get_run_records(conn, &alice, None);
+        let mut csv = Vec::new();
+        write_run_records(&records, ExportFormat::Csv, &mut csv).unwrap();
+
+        // Then every column is filled in
+        let today = Local::now().date_naive();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            format!(
+                "id,date,user,working_time_secs,breaking_time_secs,tags,task,note,uuid\n\
+                 {},{},alice,1500,300,backend;review,parser,\"done, mostly\",{}\n",
+                run_id, today, records[0].uuid
+            )
+        );
+    }
+
+    #[test]
+    fn should_export_only_runs_in_date_range() {
+        // Given a run recorded today
+        let conn = &mut in_memory_connection();
+        let alice = get_or_create_user(conn, "alice").id;
+        create_timer_run(conn, &alice, &1500, &300, None, None);
+        let today = Local::now().date_naive();
+
+        // When I ask for a range before today
+        let past = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap();
+        let records = get_run_records(conn, &alice, Some((past, past)));
+
+        // Then nothing is exported, while a range including today exports the run
+        assert!(records.is_empty());
+        assert_eq!(get_run_records(conn, &alice, Some((past, today))).len(), 1);
+    }
+
+    #[test]
+    fn should_export_runs_in_ranges_without_an_end() {
+        // Given a run recorded today
+        let conn = &mut in_memory_connection();
+        let alice = get_or_create_user(conn, "alice").id;
+        create_timer_run(conn, &alice, &1500, &300, None, None);
+        let today = Local::now().date_naive();
+
+        // When I export from a date on, the way `export <file> <from>` does
+        let past = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap();
+        let records = get_run_records(conn, &alice, Some((past, NaiveDate::MAX)));
+
+        // Then the run is exported, like it is counted for ranges without a start
+        assert_eq!(records.len(), 1);
+        assert_eq!(
+            sum_timer_runs(conn, &alice, Some((NaiveDate::MIN, today)), None).runs,
+            1
+        );
+    }
+
+    #[test]
+    fn should_sum_runs_in_sql() {
+        // Given runs on different days, one of them tagged, and a run by someone else
+        let conn = &mut in_memory_connection();
+        let alice = get_or_create_user(conn, "alice").id;
+        let bob = get_or_create_user(conn, "bob").id;
+        insert_run_on(conn, &alice, "2026-09-30", 600);
+        let tagged = insert_run_on(conn, &alice, "2026-10-01", 1500);
+        insert_run_on(conn, &alice, "2026-10-02", 1200);
+        insert_run_on(conn, &bob, "2026-10-01", 3000);
+        add_tags_to_timer_run(conn, &tagged, &["backend".to_string()]);
+
+        // When I sum them up within October and by tag
+        let october = Some((
+            NaiveDate::from_ymd_opt(2026, 10, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2026, 10, 31).unwrap(),
+        ));
+
+        // Then only the matching runs of the user count
+        assert_eq!(
+            sum_timer_runs(conn, &alice, None, None),
+            RunTotals {
+                working_time_secs: 3300,
+                breaking_time_secs: 180,
+                runs: 3
+            }
+        );
+        assert_eq!(sum_timer_runs(conn, &alice, october, None).runs, 2);
+        assert_eq!(
+            sum_timer_runs(conn, &alice, None, Some("backend")).working_time_secs,
+            1500
+        );
+        assert_eq!(
+            sum_timer_runs(conn, &alice, None, Some("frontend")),
+            RunTotals::default()
+        );
+
+        let tags = get_tag_run_totals(conn, &alice, october);
+        assert_eq!(tags.len(), 1);
+        assert_eq!((tags[0].key.as_str(), tags[0].totals.runs), ("backend", 1));
+    }
+
+    #[test]
+    fn should_group_runs_by_day_and_month() {
+        // Given runs spread over two months
+        let conn = &mut in_memory_connection();
+        let alice = get_or_create_user(conn, "alice").id;
+        insert_run_on(conn, &alice, "2026-09-30", 600);
+        insert_run_on(conn, &alice, "2026-10-01", 1500);
+        insert_run_on(conn, &alice, "2026-10-01", 1200);
+
+        // When I group them
+        let mut keys = |grouping| -> Vec<(String, i64)> {
+            get_grouped_run_totals(conn, &alice, None, grouping)
+                .into_iter()
+                .map(|group| (group.key, group.totals.working_time_secs))
+                .collect()
+        };
+
+        // Then each day or month is summed up
+        assert_eq!(
+            keys(Grouping::Day),
+            vec![
+                ("2026-09-30".to_string(), 600),
+                ("2026-10-01".to_string(), 2700)
+            ]
+        );
+        assert_eq!(
+            keys(Grouping::Month),
+            vec![("2026-09".to_string(), 600), ("2026-10".to_string(), 2700)]
+        );
+    }
+}
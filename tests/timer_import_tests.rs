@@ -0,0 +1,228 @@
+mod timer_import_tests {
+    use chrono::NaiveDate;
+    use diesel::{Connection, SqliteConnection};
+    use diesel_migrations::MigrationHarness;
+    use pomodorotimer::core::models::NewTimerRun;
+    use pomodorotimer::db::sqlite_storage::SqliteStorage;
+    use pomodorotimer::db::storage::Storage;
+    use pomodorotimer::db::timer_database::{ExportFormat, MIGRATIONS};
+    use pomodorotimer::db::timer_import::{import_runs, merge_database, parse_run_records};
+    use std::env;
+    use std::fs;
+
+    fn in_memory_storage() -> SqliteStorage {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.run_pending_migrations(MIGRATIONS).unwrap();
+        SqliteStorage::new(conn)
+    }
+
+    #[test]
+    fn should_parse_toggl_export() {
+        // Given a Toggl Track export with a broken second row
+        let csv = "User,Email,Client,Project,Task,Description,Billable,Start date,Start time,\
+                   End date,End time,Duration,Tags,Amount ()\n\
+                   Alice,a@b.c,,Backend,Parser,Wrote tests,No,2024-03-01,09:00:00,\
+                   2024-03-01,09:25:00,00:25:00,\"deep, review\",\n\
+                   Alice,a@b.c,,,,Meeting,No,yesterday,10:00:00,2024-03-01,10:30:00,00:30:00,,\n";
+
+        // When I parse it
+        let rows = parse_run_records(csv.as_bytes(), ExportFormat::Csv).unwrap();
+
+        // Then the good row is read and the bad row is reported
+        assert_eq!(rows.len(), 2);
+        let record = rows[0].as_ref().unwrap();
+        assert_eq!(record.date, NaiveDate::from_ymd_opt(2024, 3, 1).unwrap());
+        assert_eq!(record.working_time_secs, 25 * 60);
+        assert_eq!(record.breaking_time_secs, 0);
+        assert_eq!(record.tags, vec!["deep", "review", "Backend"]);
+        assert_eq!(record.task.as_deref(), Some("Parser"));
+        assert_eq!(record.note.as_deref(), Some("Wrote tests"));
+        assert_eq!(rows[1], Err("Invalid date \"yesterday\"".to_string()));
+    }
+
+    #[test]
+    fn should_reject_unknown_csv_layout() {
+        // Given a CSV file from an unknown tool
+        let csv = "when,minutes\n2024-03-01,25\n";
+
+        // Then the file is rejected as a whole
+        assert!(parse_run_records(csv.as_bytes(), ExportFormat::Csv).is_err());
+    }
+
+    #[test]
+    fn should_skip_duplicates_and_respect_dry_run() {
+        // Given an export in our own format with a repeated row
+        let storage = &mut in_memory_storage();
+        let alice = storage.get_or_create_user("alice").id;
+        let path = env::temp_dir().join(format!("pomodorotimer-import-{}.csv", std::process::id()));
+        fs::write(
+            &path,
+            "id,date,user,working_time_secs,breaking_time_secs,tags,task,note\n\
+             1,2024-03-01,bob,1500,300,backend;review,parser,\n\
+             2,2024-03-01,bob,1500,300,backend;review,parser,\n\
+             3,2024-03-02,bob,abc,300,,,\n",
+        )
+        .unwrap();
+
+        // When I do a dry run and then a real import twice
+        let dry_run = import_runs(storage, &alice, &path, ExportFormat::Csv, true).unwrap();
+        let first = import_runs(storage, &alice, &path, ExportFormat::Csv, false).unwrap();
+        let second = import_runs(storage, &alice, &path, ExportFormat::Csv, false).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        // Then the dry run saves nothing and rows are only imported once
+        assert_eq!(dry_run, first);
+        assert_eq!((first.imported, first.duplicates), (1, 1));
+        assert_eq!(
+            first.errors,
+            vec![(3, "Invalid duration \"abc\"".to_string())]
+        );
+        assert_eq!((second.imported, second.duplicates), (0, 2));
+
+        let records = storage.get_run_records(&alice, None);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].user, "alice");
+        assert_eq!(records[0].tags, vec!["backend", "review"]);
+        assert_eq!(records[0].task.as_deref(), Some("parser"));
+    }
+
+    #[test]
+    fn should_keep_uuids_that_are_not_taken() {
+        // Given an export with a new uuid, the same uuid again and a broken one
+        let storage = &mut in_memory_storage();
+        let alice = storage.get_or_create_user("alice").id;
+        let path = env::temp_dir().join(format!(
+            "pomodorotimer-import-uuid-{}.csv",
+            std::process::id()
+        ));
+        fs::write(
+            &path,
+            "id,date,user,working_time_secs,breaking_time_secs,tags,task,note,uuid\n\
+             1,2024-03-01,bob,1500,300,,,,0d4f5c4e-8a0b-4c1e-9f3a-2b7d6e5f4a3c\n\
+             2,2024-03-02,bob,1500,300,,,,0d4f5c4e-8a0b-4c1e-9f3a-2b7d6e5f4a3c\n\
+             3,2024-03-03,bob,1500,300,,,,not-a-uuid\n",
+        )
+        .unwrap();
+
+        // When I import it
+        let report = import_runs(storage, &alice, &path, ExportFormat::Csv, false).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        // Then the first run keeps its uuid and the others get new ones
+        assert_eq!(report.imported, 3);
+        let records = storage.get_run_records(&alice, None);
+        assert_eq!(records[0].uuid, "0d4f5c4e-8a0b-4c1e-9f3a-2b7d6e5f4a3c");
+        assert_ne!(records[1].uuid, records[0].uuid);
+        assert_ne!(records[2].uuid, "not-a-uuid");
+    }
+
+    #[test]
+    fn should_reject_durations_that_overflow() {
+        // Given a Toggl Track export with a duration too long to count in seconds
+        let csv = "User,Email,Client,Project,Task,Description,Billable,Start date,Start time,\
+                   End date,End time,Duration,Tags,Amount ()\n\
+                   Alice,a@b.c,,,,,No,2024-03-01,09:00:00,2024-03-01,09:25:00,999999:00:00,,\n";
+
+        // Then the row is reported instead of wrapping around
+        let rows = parse_run_records(csv.as_bytes(), ExportFormat::Csv).unwrap();
+        assert_eq!(
+            rows,
+            vec![Err("Invalid duration \"999999:00:00\"".to_string())]
+        );
+    }
+
+    #[test]
+    fn should_reject_negative_durations_in_json() {
+        // Given our own JSON export with a negative working time
+        let json = r#"[
+            {"date": "2026-10-01", "working_time_secs": -60, "breaking_time_secs": 300,
+             "task": null, "note": null},
+            {"date": "2026-10-02", "working_time_secs": 1500, "breaking_time_secs": 300,
+             "tags": [" review ", ""], "task": " ", "note": null}
+        ]"#;
+
+        // Then that row is reported like it is for CSV, and the other one is read
+        let rows = parse_run_records(json.as_bytes(), ExportFormat::Json).unwrap();
+        assert_eq!(rows[0], Err("Invalid duration \"-60\"".to_string()));
+        let record = rows[1].as_ref().unwrap();
+        assert_eq!(record.tags, vec!["review"]);
+        assert_eq!(record.task, None);
+    }
+
+    #[test]
+    fn should_merge_other_database_once() {
+        // Given two machines that recorded runs on their own, one of them on a task
+        let dir = env::temp_dir().join(format!("pomodorotimer-merge-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let laptop = &mut SqliteStorage::open(&dir.join("laptop.db"));
+        let desktop = &mut SqliteStorage::open(&dir.join("desktop.db"));
+        let alice = laptop.get_or_create_user("alice").id;
+        let task = laptop
+            .save_task(
+                &alice,
+                "parser",
+                &2,
+                &NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(),
+            )
+            .id;
+        laptop.insert_timer_run(
+            &NewTimerRun {
+                user_id: &alice,
+                working_time_secs: &1500,
+                breaking_time_secs: &300,
+                date: &NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(),
+                note: Some("on the train"),
+                task_id: Some(&task),
+                uuid: "0d4f5c4e-8a0b-4c1e-9f3a-2b7d6e5f4a3c",
+            },
+            &["backend".to_string()],
+        );
+        let bob = desktop.get_or_create_user("bob").id;
+        desktop.insert_timer_run(
+            &NewTimerRun {
+                user_id: &bob,
+                working_time_secs: &1200,
+                breaking_time_secs: &300,
+                date: &NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(),
+                note: None,
+                task_id: None,
+                uuid: "5e6f7a8b-9c0d-4e1f-8a2b-3c4d5e6f7a8b",
+            },
+            &[],
+        );
+
+        // When the laptop's database is merged into the desktop's, then both ways, then again
+        let first = merge_database(desktop, &dir.join("laptop.db")).unwrap();
+        let back = merge_database(laptop, &dir.join("desktop.db")).unwrap();
+        let again = merge_database(desktop, &dir.join("laptop.db")).unwrap();
+
+        // Then each run is added once, keeping its uuid, user, task, tags and note
+        assert_eq!((first.imported, first.duplicates), (1, 0));
+        assert_eq!((back.imported, back.duplicates), (1, 1));
+        assert_eq!((again.imported, again.duplicates), (0, 2));
+        let alice = desktop.get_or_create_user("alice").id;
+        let records = desktop.get_run_records(&alice, None);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].uuid, "0d4f5c4e-8a0b-4c1e-9f3a-2b7d6e5f4a3c");
+        assert_eq!(records[0].task.as_deref(), Some("parser"));
+        assert_eq!(records[0].tags, vec!["backend"]);
+        assert_eq!(records[0].note.as_deref(), Some("on the train"));
+        let bob = laptop.get_or_create_user("bob").id;
+        assert_eq!(laptop.get_run_records(&bob, None).len(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn should_refuse_to_merge_other_files() {
+        // Given a file that is not a database
+        let storage = &mut in_memory_storage();
+        let path = env::temp_dir().join(format!("pomodorotimer-merge-{}.txt", std::process::id()));
+        fs::write(&path, "not a database").unwrap();
+
+        // When I merge it, then nothing is merged
+        assert!(merge_database(storage, &path).is_err());
+        assert!(merge_database(storage, &path.with_extension("missing")).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,56 @@
+mod tui_app_tests {
+    use pomodorotimer::app::tui_app::{App, ReflectionTrigger};
+    use pomodorotimer::core::pomodoro_timer::TimerState;
+    use std::time::Duration;
+
+    #[test]
+    fn should_prompt_for_reflection_when_work_ends() {
+        // Given a work phase with the prompt turned on
+        let mut trigger = ReflectionTrigger::new();
+        assert!(!trigger.update(TimerState::Working, true, true));
+
+        // When the break starts, then the note is asked for once
+        assert!(trigger.update(TimerState::Breaking, true, true));
+        assert!(!trigger.update(TimerState::Breaking, true, true));
+
+        // And never with the prompt turned off
+        trigger.update(TimerState::Working, false, true);
+        assert!(!trigger.update(TimerState::Breaking, false, true));
+    }
+
+    #[test]
+    fn should_wait_with_reflection_while_typing() {
+        // Given a work phase ending while a command is being typed
+        let mut trigger = ReflectionTrigger::new();
+        trigger.update(TimerState::Working, true, false);
+        assert!(!trigger.update(TimerState::Breaking, true, false));
+        assert!(!trigger.update(TimerState::Breaking, true, false));
+
+        // When the input box is free again, then the note is asked for
+        assert!(trigger.update(TimerState::Breaking, true, true));
+        assert!(!trigger.update(TimerState::Idle, true, true));
+    }
+
+    #[test]
+    fn should_only_take_minutes_that_fit_a_run() {
+        assert_eq!(App::parse_minutes("25"), Some(1500));
+        assert_eq!(App::parse_minutes("0"), Some(0));
+        assert_eq!(App::parse_minutes("-5"), None);
+        assert_eq!(App::parse_minutes("five"), None);
+
+        // Minutes whose seconds do not fit are refused instead of overflowing
+        assert_eq!(App::parse_minutes("35791394"), Some(35_791_394 * 60));
+        assert_eq!(App::parse_minutes("35791395"), None);
+        assert_eq!(App::parse_minutes("99999999"), None);
+    }
+
+    #[test]
+    fn should_only_take_phase_minutes_that_fit_a_duration() {
+        assert_eq!(
+            App::parse_phase_minutes("5"),
+            Some(Duration::from_secs(300))
+        );
+        assert_eq!(App::parse_phase_minutes("0"), None);
+        assert_eq!(App::parse_phase_minutes("307445734561825861"), None);
+    }
+}